@@ -9,3 +9,7 @@ set -e
 set -x
 
 cargo test --all-features
+
+# The safe parser without the csv crate.
+#
+cargo test --no-default-features --features safe_csv
@@ -0,0 +1,218 @@
+//! The bodies of the fuzz targets. They live in a library so the regression tests of the main
+//! crate can replay crash inputs through exactly the same code, see `tests/fuzz.rs`.
+//!
+//! Every function takes the raw bytes of the fuzzer, and panics when something is wrong.
+//
+use {
+    arbitrary::{Arbitrary, Unstructured},
+    libtransact::*,
+    std::{borrow::Cow, collections::HashMap},
+};
+
+/// Feed arbitrary bytes to the csv parser. It should never panic, whatever the input. Both with
+/// the default dialect and a strict one, which scans the input for blank lines and long rows.
+//
+pub fn csv_parse(data: &[u8]) {
+    let strict = CsvParseBuilder::new()
+        .skip_blank_lines(false)
+        .strip_bom(false)
+        .max_row_len(Some(64))
+        .clone();
+
+    for builder in [CsvParseBuilder::new(), strict] {
+        if let Ok(parser) = builder.from_reader(data) {
+            for result in parser {
+                // Formatting errors walks the position and the csv error, which should not panic either.
+                //
+                if let Err(e) = result {
+                    let _ = e.to_string();
+                }
+            }
+        }
+    }
+}
+
+// A record with a type that is usually valid, so the fuzzer gets past the type check.
+//
+#[derive(Debug, Arbitrary)]
+//
+struct Record {
+    ttype: RecordType,
+    client: u16,
+    tx: u32,
+    amount: Option<String>,
+}
+
+#[derive(Debug, Arbitrary)]
+//
+enum RecordType {
+    Deposit,
+    Withdrawal,
+    Dispute,
+    Resolve,
+    ChargeBack,
+    Other(String),
+}
+
+/// Convert an arbitrary [`CsvRecord`] into a [`Transact`]. When it is accepted the amount must be
+/// positive, and the record must be reproduced faithfully.
+//
+pub fn transact_record(data: &[u8]) {
+    let Ok(rec) = Record::arbitrary_take_rest(Unstructured::new(data)) else {
+        return;
+    };
+
+    let ttype = match &rec.ttype {
+        RecordType::Deposit => "deposit",
+        RecordType::Withdrawal => "withdrawal",
+        RecordType::Dispute => "dispute",
+        RecordType::Resolve => "resolve",
+        RecordType::ChargeBack => "chargeback",
+        RecordType::Other(s) => s,
+    };
+
+    let record = CsvRecord::new(
+        ttype,
+        rec.client,
+        rec.tx,
+        rec.amount.as_deref().map(Cow::Borrowed),
+    );
+
+    let trans = match Transact::try_from(record) {
+        Ok(trans) => trans,
+
+        Err(e) => {
+            assert_eq!(e.kind(), TransErrKind::DeserializeTransact);
+            let _ = e.to_string();
+            return;
+        }
+    };
+
+    assert_eq!(trans.client, rec.client);
+    assert_eq!(trans.id, rec.tx);
+    assert_eq!(trans.state, TransState::New);
+
+    match trans.ttype {
+        TransType::Deposit(a) | TransType::WithDraw(a) => {
+            assert!(a > Amount::ZERO, "{a:?}");
+
+            // The amount must survive a round trip through its textual form.
+            //
+            assert_eq!(a.to_string().parse::<Amount>().ok(), Some(a));
+        }
+
+        TransType::Dispute | TransType::Resolve | TransType::ChargeBack => {
+            assert_eq!(rec.amount, None);
+        }
+
+        TransType::Unlock(_) => panic!("csv records can't be administrative"),
+    }
+}
+
+// The settings of the bank and a sequence of transactions to feed it.
+//
+#[derive(Debug, Arbitrary)]
+//
+struct Session {
+    policy: Policy,
+    withdrawal_disputes: bool,
+    locked_resolutions: bool,
+    ops: Vec<Op>,
+}
+
+#[derive(Debug, Arbitrary)]
+//
+enum Policy {
+    Reject,
+    AllowNegative,
+    Partial,
+}
+
+// Clients and transaction ids come from a small range so that transactions refer to each other.
+//
+#[derive(Debug, Arbitrary)]
+//
+enum Op {
+    Deposit { client: u8, tx: u8, amount: u128 },
+    Withdraw { client: u8, tx: u8, amount: u128 },
+    Dispute { client: u8, tx: u8 },
+    Resolve { client: u8, tx: u8 },
+    ChargeBack { client: u8, tx: u8 },
+}
+
+impl Op {
+    fn transact(&self) -> Transact {
+        // Like the csv parser, only produce positive amounts.
+        //
+        let amount = |a: u128| Amount::from_raw((a % i128::MAX as u128) as i128 + 1);
+
+        let (ttype, client, tx) = match *self {
+            Op::Deposit {
+                client,
+                tx,
+                amount: a,
+            } => (TransType::Deposit(amount(a)), client, tx),
+            Op::Withdraw {
+                client,
+                tx,
+                amount: a,
+            } => (TransType::WithDraw(amount(a)), client, tx),
+            Op::Dispute { client, tx } => (TransType::Dispute, client, tx),
+            Op::Resolve { client, tx } => (TransType::Resolve, client, tx),
+            Op::ChargeBack { client, tx } => (TransType::ChargeBack, client, tx),
+        };
+
+        Transact::new(ttype, (client % 4).into(), tx.into())
+    }
+}
+
+/// Feed an arbitrary sequence of transactions into [`Bank::process`], checking after every one
+/// that:
+///
+/// - held funds are never negative,
+/// - total is available + held,
+/// - a locked client never changes, unless resolutions on locked accounts are enabled.
+//
+pub fn bank_invariants(data: &[u8]) {
+    let Ok(session) = Session::arbitrary_take_rest(Unstructured::new(data)) else {
+        return;
+    };
+
+    let mut bank = Bank::new();
+
+    bank.set_dispute_policy(match session.policy {
+        Policy::Reject => DisputePolicy::Reject,
+        Policy::AllowNegative => DisputePolicy::AllowNegative,
+        Policy::Partial => DisputePolicy::Partial,
+    })
+    .set_withdrawal_disputes(session.withdrawal_disputes)
+    .set_locked_resolutions(session.locked_resolutions);
+
+    let mut locked: HashMap<u16, Client> = HashMap::new();
+
+    for op in &session.ops {
+        let report = bank.process(std::iter::once(Ok(op.transact())));
+
+        assert_eq!(report.processed(), 1);
+
+        for client in bank.clients().values() {
+            assert!(!client.held().is_negative(), "{op:?}: {client:?}");
+
+            assert_eq!(
+                client.available().checked_add(client.held()),
+                Some(client.total()),
+                "{op:?}: {client:?}"
+            );
+
+            if !session.locked_resolutions {
+                if let Some(before) = locked.get(&client.id()) {
+                    assert_eq!(before, client, "{op:?}");
+                }
+            }
+
+            if client.is_locked() {
+                locked.entry(client.id()).or_insert_with(|| client.clone());
+            }
+        }
+    }
+}
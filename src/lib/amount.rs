@@ -0,0 +1,356 @@
+use crate::import::*;
+
+/// A fixed point monetary amount with four decimal places, stored as an `i128` count of
+/// ten thousandths. All arithmetic is checked, overflow is reported instead of wrapping.
+///
+/// Parsing from a string accepts an optional sign, digits and up to four decimal places,
+/// eg. `"1"`, `"-2.5"`, `"0.3333"`. More decimal places are rejected rather than rounded.
+//
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+//
+pub struct Amount(i128);
+
+impl Amount {
+    /// The number of decimal places.
+    //
+    pub const SCALE: u32 = 4;
+
+    /// Zero.
+    //
+    pub const ZERO: Self = Self(0);
+
+    /// The largest representable amount.
+    //
+    pub const MAX: Self = Self(i128::MAX);
+
+    /// The smallest representable amount.
+    //
+    pub const MIN: Self = Self(i128::MIN);
+
+    const FACTOR: i128 = 10_i128.pow(Self::SCALE);
+
+    /// Create an amount from a number of ten thousandths, eg. `Amount::from_raw(15_000)` is 1.5.
+    //
+    pub const fn from_raw(raw: i128) -> Self {
+        Self(raw)
+    }
+
+    /// The number of ten thousandths in this amount.
+    //
+    pub const fn raw(self) -> i128 {
+        self.0
+    }
+
+    /// Add two amounts. Returns `None` on overflow.
+    //
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    /// Subtract two amounts. Returns `None` on overflow.
+    //
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    /// Whether this amount is smaller than zero.
+    //
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Whether this amount is zero.
+    //
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Display with exactly `decimals` decimal places, rounded with `rounding` if that is less
+    /// than [`Amount::SCALE`], eg. `1.5` with 2 decimals is `1.50`.
+    //
+    pub fn fixed(self, decimals: u32, rounding: Rounding) -> FixedAmount {
+        FixedAmount {
+            amount: self,
+            decimals,
+            rounding,
+        }
+    }
+}
+
+/// How to round an amount to fewer decimal places, see [`Amount::fixed`].
+//
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+//
+pub enum Rounding {
+    /// To the nearest, ties to the even neighbour, eg. `0.125` becomes `0.12` and `0.135`
+    /// becomes `0.14`. Also known as banker's rounding.
+    //
+    #[default]
+    HalfEven,
+
+    /// To the nearest, ties away from zero, eg. `0.125` becomes `0.13` and `-0.125` becomes
+    /// `-0.13`.
+    //
+    HalfUp,
+
+    /// Drop the extra decimals, eg. `0.129` becomes `0.12` and `-0.129` becomes `-0.12`.
+    //
+    TowardZero,
+
+    /// Round up any extra decimals, eg. `0.121` becomes `0.13` and `-0.121` becomes `-0.13`.
+    //
+    AwayFromZero,
+}
+
+/// An [`Amount`] displayed with a fixed number of decimal places, see [`Amount::fixed`].
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//
+pub struct FixedAmount {
+    amount: Amount,
+    decimals: u32,
+    rounding: Rounding,
+}
+
+impl fmt::Display for FixedAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Work on the magnitude in a u128, so rounding up can't overflow.
+        //
+        let mut raw = self.amount.0.unsigned_abs();
+        let factor = Amount::FACTOR as u128;
+
+        // Round to a multiple of `unit`, the value of the last decimal to keep.
+        //
+        if self.decimals < Amount::SCALE {
+            let unit = 10_u128.pow(Amount::SCALE - self.decimals);
+            let (q, r) = (raw / unit, raw % unit);
+
+            let up = match self.rounding {
+                Rounding::HalfEven => r > unit / 2 || (r == unit / 2 && q % 2 == 1),
+                Rounding::HalfUp => r >= unit / 2,
+                Rounding::TowardZero => false,
+                Rounding::AwayFromZero => r > 0,
+            };
+
+            raw = (q + up as u128) * unit;
+        }
+
+        let sign = if self.amount.0 < 0 && raw != 0 {
+            "-"
+        } else {
+            ""
+        };
+        let int = raw / factor;
+        let mut frac = format!("{:04}", raw % factor);
+
+        frac.truncate(self.decimals as usize);
+
+        while frac.len() < self.decimals as usize {
+            frac.push('0');
+        }
+
+        match self.decimals {
+            0 => f.pad(&format!("{sign}{int}")),
+            _ => f.pad(&format!("{sign}{int}.{frac}")),
+        }
+    }
+}
+
+/// Formats without trailing zeros, eg. `1.5`, `0`, `-0.3333`.
+//
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let int = (self.0 / Self::FACTOR).unsigned_abs();
+        let frac = (self.0 % Self::FACTOR).unsigned_abs();
+
+        let s = if frac == 0 {
+            format!("{sign}{int}")
+        } else {
+            let frac = format!("{frac:04}");
+            format!("{sign}{int}.{}", frac.trim_end_matches('0'))
+        };
+
+        f.pad(&s)
+    }
+}
+
+impl fmt::Debug for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Amount({self})")
+    }
+}
+
+/// The reasons a string can not be parsed into an [`Amount`].
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//
+pub enum ParseAmountError {
+    /// The string is not a decimal number.
+    //
+    Invalid,
+
+    /// The number has more than four decimal places.
+    //
+    TooManyDecimals,
+
+    /// The number is too large to be represented.
+    //
+    Overflow,
+}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid => write!(f, "not a valid decimal number"),
+            Self::TooManyDecimals => write!(
+                f,
+                "more than {} decimal places are not supported",
+                Amount::SCALE
+            ),
+            Self::Overflow => write!(f, "amount is too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let (int, frac) = match digits.split_once('.') {
+            Some((int, frac)) => (int, frac),
+            None => (digits, ""),
+        };
+
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+
+        if (int.is_empty() && frac.is_empty()) || !is_digits(int) || !is_digits(frac) {
+            return Err(ParseAmountError::Invalid);
+        }
+
+        if frac.len() > Self::SCALE as usize {
+            return Err(ParseAmountError::TooManyDecimals);
+        }
+
+        // Pad the decimals to the scale, so "1.5" becomes 15000.
+        //
+        let raw = int
+            .bytes()
+            .chain(frac.bytes())
+            .chain(std::iter::repeat_n(b'0', Self::SCALE as usize - frac.len()))
+            .try_fold(0_i128, |acc, b| {
+                acc.checked_mul(10)?.checked_add((b - b'0') as i128)
+            })
+            .ok_or(ParseAmountError::Overflow)?;
+
+        Ok(Self(if negative { -raw } else { raw }))
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <Cow<'de, str>>::deserialize(deserializer)?;
+
+        Amount::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+//
+mod test {
+    use {super::*, pretty_assertions::assert_eq};
+
+    #[test]
+    fn parse() {
+        assert_eq!(Amount::from_str("1"), Ok(Amount(10_000)));
+        assert_eq!(Amount::from_str("1.5"), Ok(Amount(15_000)));
+        assert_eq!(Amount::from_str("-0.3333"), Ok(Amount(-3_333)));
+        assert_eq!(Amount::from_str("+.5"), Ok(Amount(5_000)));
+        assert_eq!(Amount::from_str("2."), Ok(Amount(20_000)));
+
+        assert_eq!(
+            Amount::from_str("1.00001"),
+            Err(ParseAmountError::TooManyDecimals)
+        );
+        assert_eq!(Amount::from_str(""), Err(ParseAmountError::Invalid));
+        assert_eq!(Amount::from_str("."), Err(ParseAmountError::Invalid));
+        assert_eq!(Amount::from_str("1e3"), Err(ParseAmountError::Invalid));
+        assert_eq!(Amount::from_str("--1"), Err(ParseAmountError::Invalid));
+        assert_eq!(
+            Amount::from_str(&"9".repeat(40)),
+            Err(ParseAmountError::Overflow)
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Amount(15_000).to_string(), "1.5");
+        assert_eq!(Amount(0).to_string(), "0");
+        assert_eq!(Amount(-3_333).to_string(), "-0.3333");
+        assert_eq!(Amount(10_000).to_string(), "1");
+        assert_eq!(format!("{:>5}", Amount(15_000)), "  1.5");
+    }
+
+    #[test]
+    fn fixed() {
+        let fixed = |s, decimals, rounding| {
+            Amount::from_str(s)
+                .unwrap()
+                .fixed(decimals, rounding)
+                .to_string()
+        };
+
+        assert_eq!(fixed("1.5", 2, Rounding::HalfEven), "1.50");
+        assert_eq!(fixed("1.5", 6, Rounding::HalfEven), "1.500000");
+        assert_eq!(fixed("-0.3333", 4, Rounding::HalfEven), "-0.3333");
+
+        assert_eq!(fixed("0.125", 2, Rounding::HalfEven), "0.12");
+        assert_eq!(fixed("0.135", 2, Rounding::HalfEven), "0.14");
+        assert_eq!(fixed("-0.125", 2, Rounding::HalfEven), "-0.12");
+        assert_eq!(fixed("0.1251", 2, Rounding::HalfEven), "0.13");
+
+        assert_eq!(fixed("0.125", 2, Rounding::HalfUp), "0.13");
+        assert_eq!(fixed("-0.125", 2, Rounding::HalfUp), "-0.13");
+        assert_eq!(fixed("0.1249", 2, Rounding::HalfUp), "0.12");
+
+        assert_eq!(fixed("0.129", 2, Rounding::TowardZero), "0.12");
+        assert_eq!(fixed("-0.129", 2, Rounding::TowardZero), "-0.12");
+
+        assert_eq!(fixed("0.121", 2, Rounding::AwayFromZero), "0.13");
+        assert_eq!(fixed("-0.121", 2, Rounding::AwayFromZero), "-0.13");
+
+        assert_eq!(fixed("2.5", 0, Rounding::HalfEven), "2");
+        assert_eq!(fixed("9.9999", 2, Rounding::HalfUp), "10.00");
+        assert_eq!(fixed("-0.001", 2, Rounding::HalfEven), "0.00");
+        assert_eq!(
+            format!("{:>6}", Amount(15_000).fixed(2, Rounding::HalfEven)),
+            "  1.50"
+        );
+
+        assert_eq!(
+            Amount::MAX.fixed(0, Rounding::AwayFromZero).to_string(),
+            "17014118346046923173168730371588411"
+        );
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(Amount::MAX.checked_add(Amount(1)), None);
+        assert_eq!(Amount::MIN.checked_sub(Amount(1)), None);
+        assert_eq!(Amount(1).checked_sub(Amount(2)), Some(Amount(-1)));
+    }
+}
@@ -1,17 +1,89 @@
 use crate::{import::*, *};
+use std::time::Instant;
 
 /// The central unit that processes transactions and keeps client balances.
+///
+/// Past transactions and client accounts are kept in a [`TransactionStore`] and an [`AccountStore`].
+/// By default both are in-memory `HashMap`s. Use [`Bank::with_stores`] to provide other backends.
 //
 #[derive(Debug, Default)]
 //
-pub struct Bank {
-    clients: HashMap<u16, Client>,
+pub struct Bank<T = HashMap<u32, Transact>, A = HashMap<u16, Client>> {
+    pub(crate) clients: A,
 
-    // This mocks a DB. Since we need access to past transactions for dispute, resolve and chargeback,
-    // there isn't much of a choice here.
+    // Since we need access to past transactions for dispute, resolve and chargeback,
+    // we have to keep them around.
     //
-    db: HashMap<u32, Transact>,
-    errors: Vec<TransErr>,
+    pub(crate) db: T,
+
+    // Only filled when `Settings::retain_errors` is set, or when restored from a snapshot.
+    //
+    pub(crate) errors: Vec<TransErr>,
+
+    settings: Settings,
+}
+
+// Configuration of the bank, passed to the transaction handlers.
+//
+#[derive(Debug, Clone, Copy, Default)]
+//
+struct Settings {
+    dispute_policy: DisputePolicy,
+    withdrawal_disputes: bool,
+    locked_resolutions: bool,
+    retain_errors: bool,
+}
+
+/// What to do when a client disputes a deposit but no longer has enough available funds
+/// to cover it, eg. because they withdrew part of it since.
+//
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+//
+pub enum DisputePolicy {
+    /// Reject the dispute with [`TransErr::InsufficientFunds`].
+    //
+    #[default]
+    Reject,
+
+    /// Hold the full disputed amount, letting the available funds go negative.
+    //
+    AllowNegative,
+
+    /// Only hold what is still available. A resolve or chargeback later only releases or
+    /// removes the amount that was actually held.
+    //
+    Partial,
+}
+
+/// The outcome of successfully applying a transaction, see [`Bank::apply`].
+//
+#[derive(Clone, PartialEq, Debug)]
+//
+pub struct Receipt {
+    /// The transaction that was applied.
+    //
+    pub trans: Transact,
+
+    /// The state of the affected transaction after application. For a dispute, resolve or
+    /// chargeback this is the state of the transaction they refer to.
+    //
+    pub state: TransState,
+
+    /// The available funds of the client after application.
+    //
+    pub available: Amount,
+
+    /// The held funds of the client after application.
+    //
+    pub held: Amount,
+
+    /// The total funds of the client after application.
+    //
+    pub total: Amount,
+
+    /// Whether the client account is locked after application.
+    //
+    pub locked: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -22,98 +94,317 @@ enum Resolution {
 }
 
 impl Bank {
-    /// Create a new bank.
+    /// Create a new bank with in-memory storage.
     //
     pub fn new() -> Self {
+        Self::with_stores(HashMap::new(), HashMap::new())
+    }
+}
+
+impl<T: TransactionStore, A: AccountStore> Bank<T, A> {
+    /// Create a new bank backed by the given transaction and account stores.
+    //
+    pub fn with_stores(db: T, clients: A) -> Self {
         Self {
-            db: HashMap::new(),
-            clients: HashMap::new(),
+            db,
+            clients,
             errors: Vec::new(),
+            settings: Settings::default(),
         }
     }
 
+    /// Set how to handle disputes when the client no longer has enough available funds.
+    /// Defaults to [`DisputePolicy::Reject`].
+    //
+    pub fn set_dispute_policy(&mut self, policy: DisputePolicy) -> &mut Self {
+        self.settings.dispute_policy = policy;
+        self
+    }
+
+    /// How disputes are handled when the client no longer has enough available funds.
+    //
+    pub fn dispute_policy(&self) -> DisputePolicy {
+        self.settings.dispute_policy
+    }
+
+    /// Allow disputing withdrawals, eg. when a client claims they never made it. Disabled by default,
+    /// in which case disputing a withdrawal fails with [`TransErr::ShouldBeDeposit`].
+    ///
+    /// A disputed withdrawal credits the held funds with the amount withdrawn. A resolve means the
+    /// withdrawal stands and removes the held funds again. A chargeback reverses the withdrawal,
+    /// moving the held funds to available, and locks the account like any chargeback.
+    //
+    pub fn set_withdrawal_disputes(&mut self, allow: bool) -> &mut Self {
+        self.settings.withdrawal_disputes = allow;
+        self
+    }
+
+    /// Whether withdrawals can be disputed.
+    //
+    pub fn withdrawal_disputes(&self) -> bool {
+        self.settings.withdrawal_disputes
+    }
+
+    /// Allow resolving and charging back disputes that are already open on a locked account.
+    /// Disabled by default, in which case they fail with [`TransErr::AccountLocked`] like any other
+    /// transaction on a locked account.
+    ///
+    /// Without this, when a client has several disputed transactions and one is charged back, the
+    /// funds of the others stay held forever. Deposits, withdrawals and new disputes remain blocked.
+    //
+    pub fn set_locked_resolutions(&mut self, allow: bool) -> &mut Self {
+        self.settings.locked_resolutions = allow;
+        self
+    }
+
+    /// Whether open disputes can be resolved or charged back on a locked account.
+    //
+    pub fn locked_resolutions(&self) -> bool {
+        self.settings.locked_resolutions
+    }
+
+    /// Keep the errors of every batch in the bank, so they are available through [`Bank::errors`]
+    /// and included in snapshots. Disabled by default, in which case the errors are only returned in
+    /// the [`ProcessReport`] of each batch. When enabled, the errors are moved into the bank and the
+    /// report only counts them.
+    ///
+    /// Note that retained errors are never dropped, so memory grows with the number of errors.
+    //
+    pub fn set_retain_errors(&mut self, retain: bool) -> &mut Self {
+        self.settings.retain_errors = retain;
+        self
+    }
+
+    /// Whether the bank retains errors.
+    //
+    pub fn retain_errors(&self) -> bool {
+        self.settings.retain_errors
+    }
+
     /// Get all the clients and their balances.
     //
-    pub fn clients(&self) -> &HashMap<u16, Client> {
+    pub fn clients(&self) -> &A {
         &self.clients
     }
 
     /// Get all the clients (mutable) and their balances.
     //
-    pub fn clients_mut(&mut self) -> &mut HashMap<u16, Client> {
+    pub fn clients_mut(&mut self) -> &mut A {
         &mut self.clients
     }
 
-    /// Process a list of transactions. Will return a list of all the errors that happened
-    /// during processing. Transactions that cause an error will not affect any balances.
+    /// Get the transaction store.
+    //
+    pub fn transactions(&self) -> &T {
+        &self.db
+    }
+
+    /// The errors retained by the bank, see [`Bank::set_retain_errors`].
+    //
+    pub fn errors(&self) -> &[TransErr] {
+        &self.errors
+    }
+
+    /// Process a list of transactions. Returns a report of this batch, including the errors that
+    /// happened during processing. Transactions that cause an error will not affect any balances.
+    ///
+    /// This keeps all errors of the batch in memory. Use [`Bank::process_into`] to handle them as
+    /// they happen instead.
+    ///
+    /// Administrative transactions like [`TransType::Unlock`] are rejected with [`TransErr::Unauthorized`].
+    /// Use [`Bank::process_admin`] for those.
     //
     pub fn process(
         &mut self,
         source: impl Iterator<Item = Result<Transact, TransErr>>,
-    ) -> &[TransErr] {
+    ) -> ProcessReport {
+        let mut errors = Vec::new();
+        let mut report = self.process_from(source, false, &mut errors);
+
+        report.errors = errors;
+        report
+    }
+
+    /// Like [`Bank::process`], but every error is passed to `sink` as soon as it happens. The
+    /// [`ProcessReport::errors`] of the returned report stay empty. If the bank retains errors,
+    /// they go to the bank rather than to the sink.
+    //
+    pub fn process_into(
+        &mut self,
+        source: impl Iterator<Item = Result<Transact, TransErr>>,
+        mut sink: impl ErrorSink,
+    ) -> ProcessReport {
+        self.process_from(source, false, &mut sink)
+    }
+
+    /// Process a list of transactions from a privileged source. Like [`Bank::process`] but
+    /// administrative transactions like [`TransType::Unlock`] are accepted. Never feed this with input
+    /// from partners.
+    //
+    pub fn process_admin(
+        &mut self,
+        source: impl Iterator<Item = Result<Transact, TransErr>>,
+    ) -> ProcessReport {
+        let mut errors = Vec::new();
+        let mut report = self.process_from(source, true, &mut errors);
+
+        report.errors = errors;
+        report
+    }
+
+    /// Unlock a client account that was locked after a chargeback. The unlock is recorded in the
+    /// transaction store under the transaction id `tx` with the given reason, so it can be audited.
+    //
+    pub fn unlock(
+        &mut self,
+        client: u16,
+        tx: u32,
+        reason: impl Into<String>,
+    ) -> Result<Receipt, TransErr> {
+        self.apply_from(
+            Transact::new(TransType::Unlock(reason.into()), client, tx),
+            true,
+        )
+    }
+
+    /// Apply a single transaction. On success the receipt tells the resulting state and the balances
+    /// of the client. A transaction that fails does not change any balances. The error is returned,
+    /// it is not accumulated in [`Bank::errors`].
+    ///
+    /// Like [`Bank::process`], administrative transactions are rejected.
+    //
+    pub fn apply(&mut self, trans: Transact) -> Result<Receipt, TransErr> {
+        self.apply_from(trans, false)
+    }
+
+    /// Lazily apply the transactions from `source`, yielding the outcome of each one. Errors, including
+    /// those from the source, are yielded rather than accumulated in [`Bank::errors`].
+    //
+    pub fn process_iter<'a>(
+        &'a mut self,
+        source: impl Iterator<Item = Result<Transact, TransErr>> + 'a,
+    ) -> impl Iterator<Item = Result<Receipt, TransErr>> + 'a {
+        source.map(move |result| result.and_then(|trans| self.apply(trans)))
+    }
+
+    fn process_from(
+        &mut self,
+        source: impl Iterator<Item = Result<Transact, TransErr>>,
+        privileged: bool,
+        sink: &mut impl ErrorSink,
+    ) -> ProcessReport {
+        let start = Instant::now();
+        let mut report = ProcessReport::default();
+        let mut touched = HashSet::new();
+
         // For each transaction.
         //
         for result in source {
-            let trans = match result {
-                Ok(t) => t,
+            match result.and_then(|trans| self.apply_from(trans, privileged)) {
+                Ok(receipt) => {
+                    report.succeeded += 1;
+                    touched.insert(receipt.trans.client);
+                }
 
                 Err(e) => {
-                    self.errors.push(e);
-                    continue;
-                }
-            };
+                    report.failed += 1;
+                    *report.failed_by_kind.entry(e.kind()).or_default() += 1;
 
-            // Get the client for this transaction. If it is a deposit we can create them, otherwise they
-            // should already exist.
-            //
-            let client = if matches!(trans.ttype, TransType::Deposit(_)) {
-                self.clients
-                    .entry(trans.client)
-                    .or_insert_with(|| Client::new(trans.client))
-            } else {
-                match self.clients.get_mut(&trans.client) {
-                    Some(c) => c,
-
-                    None => {
-                        self.errors.push(TransErr::NoClient { trans });
-                        continue;
+                    if self.settings.retain_errors {
+                        self.errors.push(e);
+                    } else {
+                        sink.report(e);
                     }
                 }
-            };
+            }
+        }
+
+        report.clients_touched = touched.len();
+        report.elapsed = start.elapsed();
 
-            // client account should not be locked. No operation shall happen on a locked account.
-            //
-            if client.is_locked() {
-                self.errors.push(TransErr::AccountLocked { trans });
-                continue;
+        report
+    }
+
+    fn apply_from(&mut self, trans: Transact, privileged: bool) -> Result<Receipt, TransErr> {
+        // Administrative transactions must come from a privileged source.
+        //
+        if trans.ttype.is_admin() && !privileged {
+            return Err(TransErr::Unauthorized { trans });
+        }
+
+        // Get the client for this transaction. If it is a deposit we can create them, otherwise they
+        // should already exist.
+        //
+        let client = if matches!(trans.ttype, TransType::Deposit(_)) {
+            self.clients.get_or_create(trans.client)
+        } else {
+            match self.clients.get_mut(trans.client) {
+                Some(c) => c,
+                None => return Err(TransErr::NoClient { trans }),
             }
+        };
 
-            // Handle each type of transaction.
-            //
-            let _ = match &trans.ttype {
-                TransType::Deposit(amount) => {
-                    Self::deposit(&mut self.db, client, trans.clone(), amount.clone())
-                        .map_err(|e| self.errors.push(e))
-                }
-                TransType::WithDraw(amount) => {
-                    Self::withdraw(&mut self.db, client, trans.clone(), amount.clone())
-                        .map_err(|e| self.errors.push(e))
-                }
-                TransType::Dispute => {
-                    Self::dispute(&mut self.db, client, trans).map_err(|e| self.errors.push(e))
-                }
-                TransType::Resolve => {
-                    Self::resolution(&mut self.db, client, trans, Resolution::Resolve)
-                        .map_err(|e| self.errors.push(e))
-                }
-                TransType::ChargeBack => {
-                    Self::resolution(&mut self.db, client, trans, Resolution::ChargeBack)
-                        .map_err(|e| self.errors.push(e))
-                }
+        // client account should not be locked. No operation shall happen on a locked account,
+        // except unlocking it and, if enabled, settling open disputes.
+        //
+        if client.is_locked() {
+            let allowed = match trans.ttype {
+                TransType::Unlock(_) => true,
+                TransType::Resolve | TransType::ChargeBack => self.settings.locked_resolutions,
+                _ => false,
             };
+
+            if !allowed {
+                return Err(TransErr::AccountLocked { trans });
+            }
         }
 
-        &self.errors
+        let applied = trans.clone();
+
+        // Handle each type of transaction.
+        //
+        match trans.ttype {
+            TransType::Deposit(amount) => Self::deposit(&mut self.db, client, trans, amount),
+            TransType::WithDraw(amount) => Self::withdraw(&mut self.db, client, trans, amount),
+
+            TransType::Dispute => Self::dispute(&mut self.db, self.settings, client, trans),
+
+            TransType::Resolve => Self::resolution(
+                &mut self.db,
+                self.settings,
+                client,
+                trans,
+                Resolution::Resolve,
+            ),
+
+            TransType::ChargeBack => Self::resolution(
+                &mut self.db,
+                self.settings,
+                client,
+                trans,
+                Resolution::ChargeBack,
+            ),
+
+            TransType::Unlock(ref reason) => {
+                let reason = reason.clone();
+                Self::unlock_client(&mut self.db, client, trans, reason)
+            }
+        }?;
+
+        let state = match applied.ttype {
+            TransType::Dispute => TransState::Disputed,
+            TransType::ChargeBack => TransState::ChargedBack,
+            _ => TransState::Success,
+        };
+
+        Ok(Receipt {
+            trans: applied,
+            state,
+            available: client.available(),
+            held: client.held(),
+            total: client.total(),
+            locked: client.is_locked(),
+        })
     }
 
     /// Effectuate a deposit.
@@ -127,22 +418,34 @@ impl Bank {
     ///
     //
     fn deposit(
-        db: &mut HashMap<u32, Transact>,
+        db: &mut T,
         client: &mut Client,
         mut trans: Transact,
-        amount: BigDecimal,
+        amount: Amount,
     ) -> Result<(), TransErr> {
         // the transaction id should not exist
         //
-        if db.get(&trans.id).is_some() {
+        if db.contains(trans.id)? {
             return Err(TransErr::DuplicateTransact { trans });
         }
 
-        client.available += amount;
+        // the new balances should be representable
+        //
+        let available = match (
+            client.available.checked_add(amount),
+            client.total().checked_add(amount),
+        ) {
+            (Some(available), Some(_)) => available,
+            _ => return Err(TransErr::Overflow { trans }),
+        };
 
+        // Store first, so the balance doesn't change if that fails.
+        //
         trans.state = TransState::Success;
-        db.insert(trans.id, trans);
+        trans.clear_input();
+        db.insert(trans)?;
 
+        client.available = available;
         Ok(())
     }
 
@@ -161,14 +464,14 @@ impl Bank {
     ///
     //
     fn withdraw(
-        db: &mut HashMap<u32, Transact>,
+        db: &mut T,
         client: &mut Client,
         mut trans: Transact,
-        amount: BigDecimal,
+        amount: Amount,
     ) -> Result<(), TransErr> {
         // the transaction id should not exist
         //
-        if db.get(&trans.id).is_some() {
+        if db.contains(trans.id)? {
             return Err(TransErr::DuplicateTransact { trans });
         }
 
@@ -178,10 +481,18 @@ impl Bank {
             return Err(TransErr::InsufficientFunds { trans });
         }
 
-        client.available -= amount;
+        let available = match client.available.checked_sub(amount) {
+            Some(available) => available,
+            None => return Err(TransErr::Overflow { trans }),
+        };
 
+        // Store first, so the balance doesn't change if that fails.
+        //
         trans.state = TransState::Success;
-        db.insert(trans.id, trans);
+        trans.clear_input();
+        db.insert(trans)?;
+
+        client.available = available;
         Ok(())
     }
 
@@ -197,30 +508,34 @@ impl Bank {
     /// and assume this is an error on our partners side.
     ///
     /// Note that the spec do not mention what to do in case the available funds are insufficient.
-    /// I shall consider that we cannot re-imburse funds that the client has already spent, thus the dispute
-    /// will be ignored.
+    /// By default we consider that we cannot re-imburse funds that the client has already spent, thus the
+    /// dispute will be ignored. See [`DisputePolicy`] for the alternatives.
     ///
     /// The specs do mention this: For example, a malicious actor may try to deposit fiat funds,
     /// purchase and withdraw BTC, and then reverse their fiat deposit.
     ///
     /// I assume that refers to this situation.
     ///
+    /// Disputing a withdrawal, if enabled, credits the held funds with the withdrawn amount and leaves
+    /// the available funds alone, so the total increases.
+    ///
     /// Constraints:
     ///
     /// - transaction should exist
-    /// - transaction should be a deposit
+    /// - transaction should be a deposit, or a withdrawal if enabled
     /// - transaction should be successful
     /// - client should equal client of disputed transaction.
-    /// - client.available >= disputed amount
+    /// - client.available >= disputed amount for deposits, unless the policy allows otherwise
     //
     fn dispute(
-        db: &mut HashMap<u32, Transact>,
+        db: &mut T,
+        settings: Settings,
         client: &mut Client,
         trans: Transact,
     ) -> Result<(), TransErr> {
         // transaction should exist
         //
-        let old_trans = match db.get_mut(&trans.id) {
+        let old_trans = match db.get(trans.id)? {
             Some(t) => t,
             None => return Err(TransErr::ReferNoneExisting { trans }),
         };
@@ -231,30 +546,58 @@ impl Bank {
             return Err(TransErr::WrongClient { trans });
         }
 
-        // transaction should be a deposit
+        // transaction should be a deposit, or a withdrawal if those can be disputed
         //
-        let amount = match &old_trans.ttype {
-            TransType::Deposit(a) => a.clone(),
+        let (amount, withdrawal) = match old_trans.ttype {
+            TransType::Deposit(a) => (a, false),
+            TransType::WithDraw(a) if settings.withdrawal_disputes => (a, true),
             _ => return Err(TransErr::ShouldBeDeposit { trans }),
         };
 
-        // deposit should be in successful state
+        // transaction should be in successful state
         //
         if old_trans.state != TransState::Success {
             return Err(TransErr::WrongTransState { trans });
         }
 
         // client.available should be >= disputed amount
-        // If the client has already consumed the funds, they cannot dispute the deposit.
+        // If the client has already consumed the funds, it depends on the policy how much we hold.
+        // This does not apply to withdrawals, as they don't take funds from available.
         //
-        if client.available() < amount {
-            return Err(TransErr::InsufficientFunds { trans });
-        }
+        let hold = if withdrawal || client.available() >= amount {
+            amount
+        } else {
+            match settings.dispute_policy {
+                DisputePolicy::Reject => return Err(TransErr::InsufficientFunds { trans }),
+                DisputePolicy::AllowNegative => amount,
+                DisputePolicy::Partial => client.available().max(Amount::ZERO),
+            }
+        };
+
+        // A disputed deposit moves funds from available to held. A disputed withdrawal credits held
+        // with the funds that left the account, so the total increases.
+        //
+        let (debit, credit) = match withdrawal {
+            false => (hold, Amount::ZERO),
+            true => (Amount::ZERO, hold),
+        };
+
+        let (available, held) = match (
+            client.available.checked_sub(debit),
+            client.held.checked_add(hold),
+            client.total().checked_add(credit),
+        ) {
+            (Some(available), Some(held), Some(_)) => (available, held),
+            _ => return Err(TransErr::Overflow { trans }),
+        };
+
+        // Store first, so the balances don't change if that fails.
+        //
+        db.set_disputed(trans.id, hold)?;
 
-        client.available -= &amount;
-        client.held += amount;
+        client.available = available;
+        client.held = held;
 
-        old_trans.state = TransState::Disputed;
         Ok(())
     }
 
@@ -277,22 +620,29 @@ impl Bank {
     /// specify an amount. Like a resolve, if the tx specified doesn't exist, or the tx isn't under dispute,
     /// you can ignore chargeback and assume this is an error on our partner's side.
     ///
+    /// Only the amount that was actually held by the dispute is released or removed, which can be less
+    /// than the disputed amount with [`DisputePolicy::Partial`].
+    ///
+    /// For a disputed withdrawal it is the other way around: a resolve means the withdrawal stands and
+    /// removes the held funds, a chargeback reverses the withdrawal and moves the held funds to available.
+    ///
     /// Constraints:
     ///
     /// - transaction should exist
     /// - transaction should be disputed
     /// - client should equal client of disputed transaction.
-    /// - client.held >= disputed amount
+    /// - client.held >= held amount
     //
     fn resolution(
-        db: &mut HashMap<u32, Transact>,
+        db: &mut T,
+        settings: Settings,
         client: &mut Client,
         trans: Transact,
         action: Resolution,
     ) -> Result<(), TransErr> {
         // transaction should exist
         //
-        let old_trans = match db.get_mut(&trans.id) {
+        let old_trans = match db.get(trans.id)? {
             Some(t) => t,
             None => return Err(TransErr::ReferNoneExisting { trans }),
         };
@@ -303,49 +653,107 @@ impl Bank {
             return Err(TransErr::WrongClient { trans });
         }
 
-        // transaction should be a deposit
+        // transaction should be a deposit, or a withdrawal if those can be disputed
         //
-        let amount = match &old_trans.ttype {
-            TransType::Deposit(a) => a.clone(),
+        let (amount, withdrawal) = match old_trans.ttype {
+            TransType::Deposit(a) => (a, false),
+            TransType::WithDraw(a) if settings.withdrawal_disputes => (a, true),
             _ => return Err(TransErr::ShouldBeDeposit { trans }),
         };
 
-        // deposit should be in disputed state
+        // transaction should be in disputed state
         //
         if old_trans.state != TransState::Disputed {
             return Err(TransErr::WrongTransState { trans });
         }
 
-        // client.held should be >= disputed amount
+        // The amount held by the dispute, as recorded by the store when the dispute was opened.
+        // Version 1 logs of a `FileStore` don't record it, but back then the full amount was
+        // always held.
+        //
+        let amount = db.held(trans.id)?.unwrap_or(amount);
+
+        // client.held should be >= held amount
         //
         // debug_assert because this should be impossible to hit, as we only allow resolving/chargback of disputed
         // transactions and when we dispute, we move the funds to held.
-        // If there is not enough available funds in at the time of the dispute, the dispute gets
-        // rejected, which will be caught above because the transaction state will not be disputed.
+        // If there is not enough available funds in at the time of the dispute, the policy decides how much is
+        // held or the dispute gets rejected, which will be caught above because the transaction state will not
+        // be disputed.
         //
         if client.held() < amount {
             debug_assert!(client.held() >= amount);
             return Err(TransErr::InsufficientFunds { trans });
         }
 
-        match action {
-            Resolution::Resolve => {
-                client.available += &amount;
-                client.held -= amount;
+        let held = match client.held.checked_sub(amount) {
+            Some(held) => held,
+            None => return Err(TransErr::Overflow { trans }),
+        };
 
-                old_trans.state = TransState::Success;
+        // Resolving a deposit dispute or charging back a withdrawal returns the held funds to available.
+        // Otherwise they leave the account.
+        //
+        let available = if (action == Resolution::Resolve) != withdrawal {
+            match client.available.checked_add(amount) {
+                Some(available) => available,
+                None => return Err(TransErr::Overflow { trans }),
             }
+        } else {
+            client.available
+        };
+
+        // Store first, so the balances don't change if that fails.
+        //
+        match action {
+            Resolution::Resolve => db.set_state(trans.id, TransState::Success)?,
+            Resolution::ChargeBack => db.set_state(trans.id, TransState::ChargedBack)?,
+        }
 
-            Resolution::ChargeBack => {
-                client.held -= amount;
+        client.available = available;
+        client.held = held;
 
-                old_trans.state = TransState::ChargedBack;
-                client.lock();
-            }
+        if action == Resolution::ChargeBack {
+            client.lock();
         }
 
         Ok(())
     }
+
+    /// Reinstate a locked account.
+    ///
+    /// The unlock is stored like any other transaction, so the reason can be audited later.
+    ///
+    /// Constraints:
+    ///
+    /// - the transaction id should not exist.
+    /// - the account should be locked.
+    //
+    fn unlock_client(
+        db: &mut T,
+        client: &mut Client,
+        mut trans: Transact,
+        reason: String,
+    ) -> Result<(), TransErr> {
+        // the transaction id should not exist
+        //
+        if db.contains(trans.id)? {
+            return Err(TransErr::DuplicateTransact { trans });
+        }
+
+        // the account should be locked
+        //
+        if !client.is_locked() {
+            return Err(TransErr::AccountNotLocked { trans });
+        }
+
+        trans.state = TransState::Success;
+        trans.clear_input();
+        db.insert(trans)?;
+
+        client.unlock(reason);
+        Ok(())
+    }
 }
 
 /// Some basic sanity tests. More complex scenarios will be tested in the integration tests.
@@ -359,8 +767,59 @@ mod test {
         std::str::FromStr,
     };
 
-    fn dec(s: &str) -> BigDecimal {
-        BigDecimal::from_str(s).unwrap()
+    fn dec(s: &str) -> Amount {
+        Amount::from_str(s).unwrap()
+    }
+
+    // A store whose writes fail once `fail` is set.
+    //
+    #[derive(Default)]
+    //
+    struct FailingStore {
+        db: HashMap<u32, Transact>,
+        fail: bool,
+    }
+
+    impl FailingStore {
+        fn check(&self) -> Result<(), TransErr> {
+            match self.fail {
+                false => Ok(()),
+
+                true => Err(TransErr::Store {
+                    source: io::Error::other("disk full"),
+                    path: "store".into(),
+                }),
+            }
+        }
+    }
+
+    impl TransactionStore for FailingStore {
+        fn contains(&self, id: u32) -> Result<bool, TransErr> {
+            self.db.contains(id)
+        }
+
+        fn get(&self, id: u32) -> Result<Option<Cow<'_, Transact>>, TransErr> {
+            TransactionStore::get(&self.db, id)
+        }
+
+        fn insert(&mut self, trans: Transact) -> Result<(), TransErr> {
+            self.check()?;
+            TransactionStore::insert(&mut self.db, trans)
+        }
+
+        fn set_state(&mut self, id: u32, state: TransState) -> Result<(), TransErr> {
+            self.check()?;
+            self.db.set_state(id, state)
+        }
+
+        fn set_disputed(&mut self, id: u32, held: Amount) -> Result<(), TransErr> {
+            self.check()?;
+            self.db.set_disputed(id, held)
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = Result<Cow<'_, Transact>, TransErr>> + '_> {
+            TransactionStore::iter(&self.db)
+        }
     }
 
     fn locked_client() -> Bank {
@@ -383,7 +842,7 @@ mod test {
             Ok(Transact::new(Deposit(dec("2.3")), 1, 2)),
         ];
 
-        let errs = bank.process(trs.into_iter());
+        let errs = bank.process(trs.into_iter()).errors;
         assert_eq!(errs.len(), 0);
 
         let client = bank.clients.get(&1).unwrap();
@@ -398,7 +857,7 @@ mod test {
 
         let trs: Vec<Result<_, TransErr>> = vec![Ok(Transact::new(Deposit(dec("3.2")), 1, 1))];
 
-        let errs = bank.process(trs.into_iter());
+        let errs = bank.process(trs.into_iter()).errors;
         assert_eq!(errs.len(), 1);
         assert!(matches!(errs[0], TransErr::AccountLocked { .. }));
     }
@@ -412,7 +871,7 @@ mod test {
             Ok(Transact::new(WithDraw(dec("2.0")), 1, 2)),
         ];
 
-        let errs = bank.process(trs.into_iter());
+        let errs = bank.process(trs.into_iter()).errors;
         assert_eq!(errs.len(), 0);
 
         let client = bank.clients.get(&1).unwrap();
@@ -427,7 +886,7 @@ mod test {
 
         let trs: Vec<Result<_, TransErr>> = vec![Ok(Transact::new(WithDraw(dec("2.0")), 1, 2))];
 
-        let errs = bank.process(trs.into_iter());
+        let errs = bank.process(trs.into_iter()).errors;
         assert_eq!(errs.len(), 1);
         assert!(matches!(errs[0], TransErr::AccountLocked { .. }));
     }
@@ -438,7 +897,7 @@ mod test {
 
         let trs: Vec<Result<_, TransErr>> = vec![Ok(Transact::new(WithDraw(dec("2.0")), 1, 2))];
 
-        let errs = bank.process(trs.into_iter());
+        let errs = bank.process(trs.into_iter()).errors;
         assert_eq!(errs.len(), 1);
         assert!(matches!(errs[0], TransErr::NoClient { .. }));
     }
@@ -452,8 +911,45 @@ mod test {
             Ok(Transact::new(WithDraw(dec("4.0")), 1, 2)),
         ];
 
-        let errs = bank.process(trs.into_iter());
+        let errs = bank.process(trs.into_iter()).errors;
         assert_eq!(errs.len(), 1);
         assert!(matches!(errs[0], TransErr::InsufficientFunds { .. }));
     }
+
+    #[test]
+    fn test_store_failure() {
+        let mut bank = Bank::with_stores(FailingStore::default(), HashMap::new());
+
+        bank.apply(Transact::new(Deposit(dec("3.0")), 1, 1))
+            .unwrap();
+        bank.apply(Transact::new(Deposit(dec("1.0")), 1, 2))
+            .unwrap();
+        bank.apply(Transact::new(Dispute, 1, 2)).unwrap();
+
+        let before = bank.clients.get(&1).unwrap().clone();
+        bank.db.fail = true;
+
+        for trans in [
+            Transact::new(Deposit(dec("2.0")), 1, 3),
+            Transact::new(WithDraw(dec("1.0")), 1, 4),
+            Transact::new(Dispute, 1, 1),
+            Transact::new(Resolve, 1, 2),
+            Transact::new(ChargeBack, 1, 2),
+        ] {
+            let err = bank.apply(trans).unwrap_err();
+            assert!(matches!(err, TransErr::Store { .. }));
+        }
+
+        assert_eq!(bank.clients.get(&1).unwrap(), &before);
+        assert_eq!(bank.db.held(2).unwrap(), Some(dec("1.0")));
+
+        // Once the store works again, the dispute can still be settled.
+        //
+        bank.db.fail = false;
+        bank.apply(Transact::new(ChargeBack, 1, 2)).unwrap();
+
+        let client = bank.clients.get(&1).unwrap();
+        assert_eq!(client.total(), dec("3.0"));
+        assert!(client.is_locked());
+    }
 }
@@ -1,4 +1,5 @@
-use crate::import::*;
+use crate::{import::*, Amount};
+use serde::ser::SerializeStruct as _;
 
 /// Represents a client account.
 /// There are 2 types of balance: available and held.
@@ -7,10 +8,11 @@ use crate::import::*;
 #[derive(Clone, PartialEq, Debug)]
 //
 pub struct Client {
-    pub(crate) available: BigDecimal,
-    pub(crate) held: BigDecimal,
+    pub(crate) available: Amount,
+    pub(crate) held: Amount,
     pub(crate) id: u16,
     pub(crate) locked: bool,
+    pub(crate) unlock_reason: Option<String>,
 }
 
 impl Client {
@@ -18,9 +20,10 @@ impl Client {
     //
     pub fn new(id: u16) -> Self {
         Self {
-            available: BigDecimal::default(),
-            held: BigDecimal::default(),
+            available: Amount::ZERO,
+            held: Amount::ZERO,
             locked: false,
+            unlock_reason: None,
             id,
         }
     }
@@ -34,22 +37,25 @@ impl Client {
     /// The available funds for the client. These are the funds they dispose of
     /// for withdrawal.
     //
-    pub fn available(&self) -> BigDecimal {
-        self.available.clone()
+    pub fn available(&self) -> Amount {
+        self.available
     }
 
     /// Disputed funds are funds for a deposit the client wishes to undo.
     //
-    pub fn held(&self) -> BigDecimal {
-        self.held.clone()
+    pub fn held(&self) -> Amount {
+        self.held
     }
 
     /// The total of available and disputed funds.
-    /// This is infallible because the setters for available and held guarantee the sum
-    /// can be represented in an f64.
+    /// This is infallible because the bank rejects any transaction after which the sum
+    /// can no longer be represented in an [`Amount`].
     //
-    pub fn total(&self) -> BigDecimal {
-        &self.available + &self.held
+    pub fn total(&self) -> Amount {
+        let total = self.available.checked_add(self.held);
+
+        debug_assert!(total.is_some());
+        total.unwrap_or(Amount::MAX)
     }
 
     /// After a charge-back the account will be locked and no further transactions
@@ -65,4 +71,43 @@ impl Client {
         self.locked = true;
         self
     }
+
+    /// Reinstate this account after it was locked. Transactions will be allowed again.
+    /// Prefer [`Bank::unlock`](crate::Bank::unlock), which also records the unlock in the
+    /// transaction history.
+    //
+    pub fn unlock(&mut self, reason: impl Into<String>) -> &mut Self {
+        self.locked = false;
+        self.unlock_reason = Some(reason.into());
+        self
+    }
+
+    /// The reason given for the most recent unlock of this account, if any.
+    //
+    pub fn unlock_reason(&self) -> Option<&str> {
+        self.unlock_reason.as_deref()
+    }
+}
+
+/// Serializes with the names of the exported columns: `client`, `available`, `held`, `total` and
+/// `locked`, followed by `unlock_reason` if the account was ever unlocked. Amounts are strings,
+/// so they keep their precision.
+//
+impl Serialize for Client {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Client", 6)?;
+
+        s.serialize_field("client", &self.id)?;
+        s.serialize_field("available", &self.available)?;
+        s.serialize_field("held", &self.held)?;
+        s.serialize_field("total", &self.total())?;
+        s.serialize_field("locked", &self.locked)?;
+
+        match &self.unlock_reason {
+            Some(reason) => s.serialize_field("unlock_reason", reason)?,
+            None => s.skip_field("unlock_reason")?,
+        }
+
+        s.end()
+    }
 }
@@ -0,0 +1,190 @@
+//! Binary encoding helpers shared by the file store and bank snapshots.
+//! All integers are little endian, strings are prefixed with their length as u32.
+//
+use crate::{import::*, *};
+
+/// Append encoded values to a buffer.
+//
+#[derive(Debug, Default)]
+//
+pub(crate) struct Encoder {
+    pub(crate) buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn u8(&mut self, v: u8) -> &mut Self {
+        self.buf.push(v);
+        self
+    }
+
+    pub(crate) fn u16(&mut self, v: u16) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u32(&mut self, v: u32) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn str(&mut self, v: &str) -> &mut Self {
+        self.bytes(v.as_bytes())
+    }
+
+    pub(crate) fn bytes(&mut self, v: &[u8]) -> &mut Self {
+        self.u32(v.len() as u32);
+        self.buf.extend_from_slice(v);
+        self
+    }
+
+    pub(crate) fn amount(&mut self, v: Amount) -> &mut Self {
+        self.str(&v.to_string())
+    }
+
+    pub(crate) fn state(&mut self, v: TransState) -> &mut Self {
+        self.u8(match v {
+            TransState::New => 0,
+            TransState::Success => 1,
+            TransState::Disputed => 2,
+            TransState::ChargedBack => 3,
+        })
+    }
+
+    pub(crate) fn transact(&mut self, v: &Transact) -> &mut Self {
+        self.u32(v.id).u16(v.client).state(v.state);
+
+        match &v.ttype {
+            TransType::Deposit(a) => self.u8(0).amount(*a),
+            TransType::WithDraw(a) => self.u8(1).amount(*a),
+            TransType::Dispute => self.u8(2),
+            TransType::Resolve => self.u8(3),
+            TransType::ChargeBack => self.u8(4),
+            TransType::Unlock(reason) => self.u8(5).str(reason),
+        }
+    }
+
+    pub(crate) fn position(&mut self, v: Option<&Position>) -> &mut Self {
+        match v {
+            Some(p) => self.u8(1).u64(p.line).u64(p.byte).bytes(&p.row),
+            None => self.u8(0),
+        }
+    }
+}
+
+/// Read encoded values from a buffer. Running out of data or invalid values are reported as
+/// `io::ErrorKind::InvalidData`.
+//
+#[derive(Debug)]
+//
+pub(crate) struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < n {
+            return Err(corrupt("record too short"));
+        }
+
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> io::Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> io::Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<u64> {
+        let b = self.take(8)?;
+        Ok(u64::from_le_bytes(b.try_into().expect("8 bytes")))
+    }
+
+    pub(crate) fn str(&mut self) -> io::Result<&'a str> {
+        std::str::from_utf8(self.bytes()?).map_err(|_| corrupt("invalid utf8"))
+    }
+
+    pub(crate) fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub(crate) fn amount(&mut self) -> io::Result<Amount> {
+        Amount::from_str(self.str()?).map_err(|_| corrupt("invalid amount"))
+    }
+
+    pub(crate) fn state(&mut self) -> io::Result<TransState> {
+        match self.u8()? {
+            0 => Ok(TransState::New),
+            1 => Ok(TransState::Success),
+            2 => Ok(TransState::Disputed),
+            3 => Ok(TransState::ChargedBack),
+            _ => Err(corrupt("unknown transaction state")),
+        }
+    }
+
+    pub(crate) fn transact(&mut self) -> io::Result<Transact> {
+        let id = self.u32()?;
+        let client = self.u16()?;
+        let state = self.state()?;
+
+        let ttype = match self.u8()? {
+            0 => TransType::Deposit(self.amount()?),
+            1 => TransType::WithDraw(self.amount()?),
+            2 => TransType::Dispute,
+            3 => TransType::Resolve,
+            4 => TransType::ChargeBack,
+            5 => TransType::Unlock(self.str()?.to_string()),
+            _ => return Err(corrupt("unknown transaction type")),
+        };
+
+        Ok(Transact {
+            ttype,
+            state,
+            client,
+            id,
+            pos: None,
+            extra: Vec::new(),
+            held: Amount::ZERO,
+        })
+    }
+
+    pub(crate) fn position(&mut self) -> io::Result<Option<Box<Position>>> {
+        Ok(match self.u8()? {
+            0 => None,
+            _ => Some(Box::new(Position {
+                line: self.u64()?,
+                byte: self.u64()?,
+                row: self.bytes()?.to_vec(),
+            })),
+        })
+    }
+}
+
+pub(crate) fn corrupt(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
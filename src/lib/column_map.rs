@@ -0,0 +1,134 @@
+use crate::{import::*, Column, ExtraColumns, TransErr};
+
+// Where the fields of a transaction are in a row, resolved from a header or a `CsvLayout`.
+//
+#[derive(Debug, Clone)]
+//
+pub(crate) struct ColumnMap {
+    // The indices of type, client, tx and amount.
+    //
+    index: [usize; 4],
+
+    // The index of the optional reason column. Only `CsvParse` reads it, `SafeCsvParse` doesn't
+    // take admin input.
+    //
+    #[cfg_attr(not(feature = "csv"), allow(dead_code))]
+    //
+    reason: Option<usize>,
+
+    // Indices and names of the extra columns we pass through.
+    //
+    extra: Vec<(usize, String)>,
+
+    len: usize,
+}
+
+impl ColumnMap {
+    // Map the columns named in a header.
+    //
+    pub(crate) fn from_header<S: AsRef<str>>(
+        header: impl Iterator<Item = S>,
+        extra: ExtraColumns,
+    ) -> Result<Self, TransErr> {
+        let columns: Vec<_> = header
+            .map(|name| Column::from_name(name.as_ref()))
+            .collect();
+
+        Self::new(&columns, extra)
+    }
+
+    // Map the columns in the given order. Fails with `NoHeader` unless type, client, tx
+    // and amount each appear exactly once.
+    //
+    pub(crate) fn new(columns: &[Column], extra: ExtraColumns) -> Result<Self, TransErr> {
+        let mut index = [None; 4];
+        let mut reason = None;
+        let mut extras = Vec::new();
+
+        for (i, column) in columns.iter().enumerate() {
+            let slot = match column {
+                Column::Type => &mut index[0],
+                Column::Client => &mut index[1],
+                Column::Tx => &mut index[2],
+                Column::Amount => &mut index[3],
+                Column::Reason => &mut reason,
+
+                Column::Extra(name) => {
+                    if extra == ExtraColumns::PassThrough {
+                        extras.push((i, name.clone()));
+                    }
+                    continue;
+                }
+            };
+
+            if slot.replace(i).is_some() {
+                return Err(TransErr::NoHeader);
+            }
+        }
+
+        match index {
+            [Some(ttype), Some(client), Some(tx), Some(amount)] => Ok(Self {
+                index: [ttype, client, tx, amount],
+                reason,
+                extra: extras,
+                len: columns.len(),
+            }),
+
+            _ => Err(TransErr::NoHeader),
+        }
+    }
+
+    // The number of fields in a row.
+    //
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    // The name of the `CsvRecord` field in each column, empty for columns it doesn't read. Used as
+    // the header to deserialize rows in input order, so errors point at the column in the input.
+    //
+    #[cfg(feature = "csv")]
+    //
+    pub(crate) fn names(&self) -> Vec<&'static str> {
+        let mut names = vec![""; self.len];
+
+        for (i, name) in self.index.iter().zip(["type", "client", "tx", "amount"]) {
+            names[*i] = name;
+        }
+
+        if let Some(i) = self.reason {
+            names[i] = "reason";
+        }
+
+        names
+    }
+
+    // The fields of type, client, tx and amount, in that order.
+    //
+    #[cfg(feature = "safe_csv")]
+    //
+    pub(crate) fn fields<'a>(&self, get: impl Fn(usize) -> Option<&'a str>) -> [&'a str; 4] {
+        self.index.map(|i| get(i).unwrap_or_default())
+    }
+
+    // The names and values of the extra columns to pass through.
+    //
+    pub(crate) fn extra<'a>(
+        &self,
+        get: impl Fn(usize) -> Option<&'a str>,
+    ) -> Vec<(String, String)> {
+        self.extra
+            .iter()
+            .map(|(i, name)| (name.clone(), get(*i).unwrap_or_default().to_string()))
+            .collect()
+    }
+}
+
+// The error for a row with the wrong number of fields.
+//
+pub(crate) fn field_count(found: usize, expected: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("found record with {found} fields, but expected {expected}"),
+    )
+}
@@ -0,0 +1,171 @@
+use crate::{import::*, CsvLayout, CsvParse, TransErr};
+
+/// Configures the dialect of the csv input for [`CsvParse`].
+///
+/// The defaults are those of [`CsvParse::new`]: comma delimited, fields may be quoted with `"`,
+/// no comments, blank lines are skipped, a byte order mark is stripped and rows can have any
+/// length. Whitespace around fields is always trimmed.
+///
+/// ```
+/// use libtransact::*;
+///
+/// let input = "
+/// ## Exported by our partner.
+/// type;client;tx;amount
+/// deposit;1;1;2.5
+/// ";
+///
+/// let parser = CsvParseBuilder::new()
+///     .delimiter(b';')
+///     .comment(Some(b'#'))
+///     .from_reader(input.as_bytes())?;
+///
+/// let mut bank = Bank::new();
+/// bank.process(parser);
+///
+/// assert_eq!(bank.clients().get(&1).unwrap().total().to_string(), "2.5");
+/// # Ok::<(), TransErr>(())
+/// ```
+//
+#[derive(Debug, Clone)]
+//
+pub struct CsvParseBuilder {
+    pub(crate) delimiter: u8,
+    pub(crate) quote: u8,
+    pub(crate) quoting: bool,
+    pub(crate) comment: Option<u8>,
+    pub(crate) skip_blank_lines: bool,
+    pub(crate) strip_bom: bool,
+    pub(crate) max_row_len: Option<usize>,
+    pub(crate) layout: CsvLayout,
+    pub(crate) admin: bool,
+}
+
+impl Default for CsvParseBuilder {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            quoting: true,
+            comment: None,
+            skip_blank_lines: true,
+            strip_bom: true,
+            max_row_len: None,
+            layout: CsvLayout::default(),
+            admin: false,
+        }
+    }
+}
+
+impl CsvParseBuilder {
+    /// A builder with the default dialect.
+    //
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The field delimiter, eg. `b'\t'`, `b';'` or `b'|'`. Defaults to `b','`.
+    //
+    pub fn delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// The quote character. Defaults to `b'"'`. Within a quoted field, a quote is escaped by
+    /// doubling it.
+    //
+    pub fn quote(&mut self, quote: u8) -> &mut Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Whether fields can be quoted. When disabled, quotes are just part of the field.
+    /// Defaults to `true`.
+    //
+    pub fn quoting(&mut self, quoting: bool) -> &mut Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Rows starting with this character are ignored. Defaults to `None`.
+    //
+    pub fn comment(&mut self, comment: Option<u8>) -> &mut Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Whether blank lines are skipped. If not, each one is reported as a
+    /// [`TransErr::DeserializeCsv`]. Defaults to `true`.
+    //
+    pub fn skip_blank_lines(&mut self, skip: bool) -> &mut Self {
+        self.skip_blank_lines = skip;
+        self
+    }
+
+    /// Whether a UTF-8 byte order mark at the start of the input is stripped. If not, it is part
+    /// of the first field, so a header will not be recognized. Defaults to `true`.
+    //
+    pub fn strip_bom(&mut self, strip: bool) -> &mut Self {
+        self.strip_bom = strip;
+        self
+    }
+
+    /// The maximum length of a row in bytes, not counting the line ending. A longer row is
+    /// reported as a [`TransErr::DeserializeCsv`] and ends the input, so a malicious file can't
+    /// make us buffer an arbitrary amount of data. Defaults to `None`, which means no limit.
+    //
+    pub fn max_row_len(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_row_len = max;
+        self
+    }
+
+    /// Where to find the columns, see [`CsvLayout`].
+    //
+    pub fn layout(&mut self, layout: CsvLayout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Whether the input is privileged and may hold administrative transactions. If so, rows of
+    /// type `unlock` are read as [`TransType::Unlock`](crate::TransType::Unlock), with the reason
+    /// from the `reason` column. Feed such a parser to [`Bank::process_admin`](crate::Bank::process_admin),
+    /// never use it for input from partners. Defaults to `false`, in which case `unlock` is an
+    /// unknown type.
+    ///
+    /// ```
+    /// use libtransact::*;
+    ///
+    /// let input = "
+    /// type, client, tx, amount, reason
+    /// unlock, 1, 9, , Chargeback was fraudulent
+    /// ";
+    ///
+    /// let mut parser = CsvParseBuilder::new().admin(true).from_reader(input.trim().as_bytes())?;
+    /// let trans = parser.next().unwrap()?;
+    ///
+    /// assert_eq!(trans.ttype, TransType::Unlock("Chargeback was fraudulent".to_string()));
+    /// # Ok::<(), TransErr>(())
+    /// ```
+    //
+    pub fn admin(&mut self, admin: bool) -> &mut Self {
+        self.admin = admin;
+        self
+    }
+
+    /// Create a parser for csv data from `reader`. Reads the header, if any.
+    //
+    pub fn from_reader<R: io::Read>(&self, reader: R) -> Result<CsvParse<R>, TransErr> {
+        CsvParse::build(reader, self)
+    }
+
+    /// Open the file at `path` and create a parser for it.
+    //
+    pub fn from_path(&self, path: &Path) -> Result<CsvParse<File>, TransErr> {
+        let file = File::open(path).map_err(|e| TransErr::InputFile {
+            source: e,
+            path: path.to_path_buf(),
+        })?;
+
+        self.from_reader(file)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{import::*, *};
+use std::io::Write as _;
 
 /// Namespace for the export function. Takes care of exporting the client data to CSV.
 //
@@ -7,38 +8,84 @@ use crate::{import::*, *};
 pub struct CsvExport {}
 
 impl CsvExport {
-    /// Export the client data to CSV.
+    /// Export the client data to CSV. This holds the whole output in memory, prefer
+    /// [`CsvExport::write_to`] for large numbers of clients.
     //
-    pub fn export(clients: &HashMap<u16, Client>) -> Result<String, TransErr> {
-        let mut width = 12;
-        let mut out = String::new();
-
-        std::writeln!(
-            out,
-            "{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}",
-            "client,",
-            "available,",
-            "held,",
-            "total,",
-            "locked"
-        )
-        .map_err(|source| TransErr::SerializeClients { source })?;
-
-        width -= 1;
-
-        for (i, c) in clients {
-            std::writeln!(
-                out,
-                "{:>width$},{:>width$},{:>width$},{:>width$},{:>width$}",
-                i,
-                c.available().normalized(),
-                c.held().normalized(),
-                c.total().normalized(),
-                c.is_locked()
-            )
-            .map_err(|source| TransErr::SerializeClients { source })?;
+    pub fn export(clients: &(impl AccountStore + ?Sized)) -> Result<String, TransErr> {
+        Self::export_with(clients, &ExportOptions::default())
+    }
+
+    /// Export the client data to CSV, formatted as configured by `options`.
+    //
+    pub fn export_with(
+        clients: &(impl AccountStore + ?Sized),
+        options: &ExportOptions,
+    ) -> Result<String, TransErr> {
+        let mut out = Vec::new();
+
+        Self::write_with(clients, options, &mut out)?;
+
+        // We only write formatted numbers and booleans.
+        //
+        String::from_utf8(out).map_err(|e| TransErr::SerializeClients {
+            source: io::Error::new(io::ErrorKind::InvalidData, e),
+        })
+    }
+
+    /// Export the client data to CSV, writing each row to `out` as it is formatted. The output is
+    /// buffered, so there is no need to pass a buffered writer. Errors writing to `out` are
+    /// returned as [`TransErr::SerializeClients`].
+    //
+    pub fn write_to<W: io::Write>(
+        clients: &(impl AccountStore + ?Sized),
+        out: W,
+    ) -> Result<(), TransErr> {
+        Self::write_with(clients, &ExportOptions::default(), out)
+    }
+
+    /// Like [`CsvExport::write_to`], formatted as configured by `options`.
+    //
+    pub fn write_with<W: io::Write>(
+        clients: &(impl AccountStore + ?Sized),
+        options: &ExportOptions,
+        out: W,
+    ) -> Result<(), TransErr> {
+        let err = |source| TransErr::SerializeClients { source };
+
+        let width = 12;
+        let mut out = io::BufWriter::new(out);
+
+        // When padded, the header names include the comma, so they line up with the values.
+        //
+        for (i, column) in options.columns.iter().enumerate() {
+            let last = i + 1 == options.columns.len();
+            let comma = if last { "" } else { "," };
+
+            match options.padded {
+                true => write!(out, "{:>width$}", format!("{}{comma}", column.name())),
+                false => write!(out, "{}{comma}", column.name()),
+            }
+            .map_err(err)?;
+        }
+
+        writeln!(out).map_err(err)?;
+
+        for client in options.sorted(clients) {
+            for (i, &column) in options.columns.iter().enumerate() {
+                let value = options.value(client, column);
+
+                match (i, options.padded) {
+                    (0, true) => write!(out, "{value:>0$}", width - 1),
+                    (0, false) => write!(out, "{value}"),
+                    (_, true) => write!(out, ",{value:>0$}", width - 1),
+                    (_, false) => write!(out, ",{value}"),
+                }
+                .map_err(err)?;
+            }
+
+            writeln!(out).map_err(err)?;
         }
 
-        Ok(out)
+        out.flush().map_err(err)
     }
 }
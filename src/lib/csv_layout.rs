@@ -0,0 +1,144 @@
+/// A column of the csv input.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+//
+pub enum Column {
+    /// The type of transaction, eg. `deposit`.
+    //
+    Type,
+
+    /// The client id.
+    //
+    Client,
+
+    /// The transaction id.
+    //
+    Tx,
+
+    /// The amount of a deposit or withdrawal.
+    //
+    Amount,
+
+    /// The reason of an unlock. Only read from admin input, see
+    /// [`CsvParseBuilder::admin`](crate::CsvParseBuilder::admin). Optional.
+    //
+    Reason,
+
+    /// Any other column, with its name.
+    //
+    Extra(String),
+}
+
+impl Column {
+    /// The column for a name in the header. Names are case-insensitive, unknown names give
+    /// [`Column::Extra`].
+    //
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "type" => Self::Type,
+            "client" => Self::Client,
+            "tx" => Self::Tx,
+            "amount" => Self::Amount,
+            "reason" => Self::Reason,
+            _ => Self::Extra(name.to_string()),
+        }
+    }
+
+    /// The name of the column in a header, the inverse of [`Column::from_name`].
+    //
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Type => "type",
+            Self::Client => "client",
+            Self::Tx => "tx",
+            Self::Amount => "amount",
+            Self::Reason => "reason",
+            Self::Extra(name) => name,
+        }
+    }
+}
+
+/// What to do with columns that are not part of the transaction format.
+//
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+//
+pub enum ExtraColumns {
+    /// Ignore them.
+    //
+    #[default]
+    Ignore,
+
+    /// Keep their values in [`Transact::extra`](crate::Transact::extra).
+    //
+    PassThrough,
+}
+
+/// Describes where the parser finds the columns of the csv input.
+///
+/// By default the first row is a header naming the columns. The names are case-insensitive and
+/// the columns can be in any order, as long as `type`, `client`, `tx` and `amount` each appear
+/// once. Other columns are ignored unless [`ExtraColumns::PassThrough`] is set.
+///
+/// For input without a header, use [`CsvLayout::headerless`] to give the order of the columns.
+///
+/// ```
+/// use libtransact::*;
+///
+/// # #[cfg(feature = "csv")]
+/// # fn main() -> Result<(), TransErr> {
+/// let input = "1, 1, deposit, 2.5\n1, 2, withdrawal, 1\n";
+/// let layout = CsvLayout::headerless([Column::Client, Column::Tx, Column::Type, Column::Amount]);
+///
+/// let mut bank = Bank::new();
+/// bank.process(CsvParse::with_layout(input.as_bytes(), &layout)?);
+///
+/// assert_eq!(bank.clients().get(&1).unwrap().total().to_string(), "1.5");
+/// # Ok(())
+/// # }
+/// #
+/// # #[cfg(not(feature = "csv"))]
+/// # fn main() {}
+/// ```
+//
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+//
+pub struct CsvLayout {
+    columns: Option<Vec<Column>>,
+    extra: ExtraColumns,
+}
+
+impl CsvLayout {
+    /// The default layout: the columns are read from the header.
+    //
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The input has no header, the columns are in the given order.
+    //
+    pub fn headerless(columns: impl IntoIterator<Item = Column>) -> Self {
+        Self {
+            columns: Some(columns.into_iter().collect()),
+            extra: ExtraColumns::default(),
+        }
+    }
+
+    /// Set what to do with unknown columns. Defaults to [`ExtraColumns::Ignore`].
+    //
+    pub fn set_extra_columns(&mut self, extra: ExtraColumns) -> &mut Self {
+        self.extra = extra;
+        self
+    }
+
+    /// What is done with unknown columns.
+    //
+    pub fn extra_columns(&self) -> ExtraColumns {
+        self.extra
+    }
+
+    /// The configured order of the columns, or `None` when they are read from the header.
+    //
+    pub fn columns(&self) -> Option<&[Column]> {
+        self.columns.as_deref()
+    }
+}
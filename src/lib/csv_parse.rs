@@ -1,4 +1,13 @@
-use crate::{import::*, transaction::*, TransErr};
+use crate::{
+    column_map::{field_count, ColumnMap},
+    import::*,
+    position::{position, with_position},
+    reject_sink::Dialect,
+    row_scan::RowScan,
+    transaction::*,
+    CsvLayout, CsvParseBuilder, Position, RejectLayout, TransErr,
+};
+use std::collections::VecDeque;
 
 /// A csv source for transactions. The format is as follows:
 ///
@@ -11,56 +20,270 @@ use crate::{import::*, transaction::*, TransErr};
 /// withdrawal,      2,  5,    3.0
 /// ```
 ///
+/// The header names the columns. See [`CsvLayout`] for the accepted variations, and for input
+/// without a header.
+///
 /// CsvParse will open the file when constructed and keep it open until dropped.
+///
+/// Every transaction and every error for a row carries the [`Position`] of that row in the input.
 //
 pub struct CsvParse<T> {
-    source: csv::StringRecordsIntoIter<T>,
+    source: csv::StringRecordsIntoIter<RowScan<T>>,
+    columns: ColumnMap,
+
+    // The `CsvRecord` field of each column, see `ColumnMap::names`.
+    //
+    names: csv::StringRecord,
+
+    // The columns and dialect of the input, for writing rejected rows.
+    //
+    rejects: RejectLayout,
+
+    // Errors for blank lines come before the record that follows them.
+    //
+    pending: VecDeque<Result<Transact, TransErr>>,
+
+    // Without a header, the first record gets the byte order mark if it isn't stripped.
+    //
+    keep_bom: bool,
+    admin: bool,
+    done: bool,
 }
 
-impl<T: std::io::Read> CsvParse<T> {
-    /// Create a new file based source for Csv data.
+impl<T: io::Read> CsvParse<T> {
+    /// Create a new file based source for Csv data. Use [`CsvParseBuilder`] to configure the
+    /// dialect of the input.
     //
     pub fn new(reader: T) -> Result<Self, TransErr> {
+        CsvParseBuilder::new().from_reader(reader)
+    }
+
+    /// Create a new source for Csv data with the given layout of columns.
+    //
+    pub fn with_layout(reader: T, layout: &CsvLayout) -> Result<Self, TransErr> {
+        CsvParseBuilder::new()
+            .layout(layout.clone())
+            .from_reader(reader)
+    }
+
+    pub(crate) fn build(reader: T, builder: &CsvParseBuilder) -> Result<Self, TransErr> {
+        let layout = &builder.layout;
+
+        let scan = RowScan::new(
+            reader,
+            builder.delimiter,
+            builder.quoting.then_some(builder.quote),
+            builder.comment,
+            !builder.skip_blank_lines,
+            builder.max_row_len,
+        );
+
         let mut reader = csv::ReaderBuilder::new()
             .trim(csv::Trim::All)
-            .from_reader(reader);
+            .delimiter(builder.delimiter)
+            .quote(builder.quote)
+            .quoting(builder.quoting)
+            .comment(builder.comment)
+            .has_headers(layout.columns().is_none())
+            .from_reader(scan);
+
+        let mut pending = VecDeque::new();
+        let header: Vec<String>;
+
+        let columns = match layout.columns() {
+            Some(columns) => {
+                header = columns.iter().map(|c| c.name().to_string()).collect();
+                ColumnMap::new(columns, layout.extra_columns())?
+            }
+
+            None => {
+                let mut headers = reader
+                    .headers()
+                    .map_err(|e| TransErr::DeserializeHeader { source: e.into() })?
+                    .clone();
+
+                header = headers.iter().map(String::from).collect();
+
+                if !builder.strip_bom && reader.get_ref().bom {
+                    headers = with_bom(&headers);
+                }
+
+                let end = reader.position().byte();
+                blank_lines(reader.get_mut(), Some(end), &mut pending);
+
+                ColumnMap::from_header(headers.iter(), layout.extra_columns())?
+            }
+        };
+
+        Ok(Self {
+            source: reader.into_records(),
+            names: columns.names().into_iter().collect(),
+            rejects: RejectLayout {
+                columns: header,
+                dialect: Some(Dialect {
+                    delimiter: builder.delimiter,
+                    quote: builder.quote,
+                    quoting: builder.quoting,
+                }),
+            },
+            columns,
+            pending,
+            keep_bom: !builder.strip_bom && layout.columns().is_some(),
+            admin: builder.admin,
+            done: false,
+        })
+    }
+
+    /// The columns and dialect of the input, to write rejected rows the way they were read with
+    /// [`RejectSink::with_layout`].
+    //
+    pub fn reject_layout(&self) -> &RejectLayout {
+        &self.rejects
+    }
+
+    // Validate a record that ends at byte `end`.
+    //
+    fn record(&mut self, cr: csv::StringRecord, end: u64) -> Result<Transact, TransErr> {
+        let cr = match std::mem::take(&mut self.keep_bom) && self.source.reader().get_ref().bom {
+            true => with_bom(&cr),
+            false => cr,
+        };
 
-        let headers = reader
-            .headers()
-            .map_err(|e| TransErr::DeserializeHeader { source: e })?;
+        let (line, byte) = cr.position().map_or((0, 0), |p| (p.line(), p.byte()));
+        let row = self.source.reader_mut().get_mut().take_row(byte, end);
+        let pos = position(line, byte, row);
 
-        if headers != ["type", "client", "tx", "amount"].as_ref() {
-            return Err(TransErr::NoHeader);
+        // Without a header csv can't check the number of fields against it.
+        //
+        if cr.len() != self.columns.len() {
+            let source = field_count(cr.len(), self.columns.len()).into();
+            return with_position(Err(TransErr::DeserializeCsv { source, pos: None }), pos);
         }
 
-        let source = reader.into_records();
+        // Deserialize in input order, so an error reports the index of the field in the row.
+        //
+        let result = match cr.deserialize::<CsvRecord<'_>>(Some(&self.names)) {
+            Ok(r) => Transact::from_record(r, self.admin),
+            Err(e) => Err(TransErr::DeserializeCsv {
+                source: e.into(),
+                pos: None,
+            }),
+        };
 
-        Ok(Self { source })
+        let result = result.map(|mut trans| {
+            trans.extra = self.columns.extra(|i| cr.get(i));
+            trans
+        });
+
+        with_position(result, pos)
     }
 }
 
-impl<T: std::io::Read> Iterator for CsvParse<T> {
+impl<T: io::Read> Iterator for CsvParse<T> {
     type Item = Result<Transact, TransErr>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(result) = self.source.next() {
-            let cr = match result {
-                Ok(r) => r,
-                Err(e) => return Some(Err(TransErr::DeserializeCsv { source: e })),
-            };
-
-            match cr.deserialize::<CsvRecord<'_>>(None) {
-                Ok(r) => return Some(Transact::try_from(r)),
-                Err(e) => return Some(Err(TransErr::DeserializeCsv { source: e })),
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+
+            if self.done {
+                return None;
             }
+
+            let item = self.source.next();
+            let end = self.source.reader().position().byte();
+            let scan = self.source.reader_mut().get_mut();
+
+            match item {
+                Some(Ok(cr)) => {
+                    blank_lines(scan, Some(end), &mut self.pending);
+
+                    let result = self.record(cr, end);
+                    self.pending.push_back(result);
+                }
+
+                // A row that is too long ends the input.
+                //
+                Some(Err(source)) if scan.too_long.is_some() => {
+                    let (line, byte) = scan.too_long.unwrap_or_default();
+
+                    blank_lines(scan, None, &mut self.pending);
+
+                    self.pending.push_back(Err(TransErr::DeserializeCsv {
+                        source: source.into(),
+                        pos: Some(position(line, byte, Vec::new())),
+                    }));
+
+                    self.done = true;
+                }
+
+                // csv knows where the row starts for most errors.
+                //
+                Some(Err(source)) => {
+                    let pos = source.position().map(|p| {
+                        Box::new(Position {
+                            line: p.line(),
+                            byte: p.byte(),
+                            row: scan.take_row(p.byte(), end),
+                        })
+                    });
+
+                    self.pending.push_back(Err(TransErr::DeserializeCsv {
+                        source: source.into(),
+                        pos,
+                    }));
+                }
+
+                None => {
+                    blank_lines(scan, None, &mut self.pending);
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+// Report the blank lines found before `end`, or all of them.
+//
+fn blank_lines<T>(
+    scan: &mut RowScan<T>,
+    end: Option<u64>,
+    pending: &mut VecDeque<Result<Transact, TransErr>>,
+) {
+    while let Some(&(line, byte)) = scan.blanks.front() {
+        if end.is_some_and(|end| byte >= end) {
+            break;
         }
 
-        None
+        scan.blanks.pop_front();
+
+        pending.push_back(Err(TransErr::DeserializeCsv {
+            source: io::Error::new(io::ErrorKind::InvalidData, "blank line").into(),
+            pos: Some(position(line, byte, Vec::new())),
+        }));
     }
 }
 
+// Put back the byte order mark csv stripped from the first field.
+//
+fn with_bom(record: &csv::StringRecord) -> csv::StringRecord {
+    let mut with_bom: csv::StringRecord = record
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match i {
+            0 => format!("\u{feff}{field}"),
+            _ => field.to_string(),
+        })
+        .collect();
+
+    with_bom.set_position(record.position().cloned());
+    with_bom
+}
+
 impl<T> fmt::Debug for CsvParse<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "CsvParse")
     }
 }
@@ -68,7 +291,7 @@ impl<T> fmt::Debug for CsvParse<T> {
 impl TryFrom<&'static str> for CsvParse<&[u8]> {
     type Error = TransErr;
 
-    fn try_from(s: &'static str) -> Result<CsvParse<&[u8]>, TransErr> {
+    fn try_from(s: &'static str) -> Result<CsvParse<&'static [u8]>, TransErr> {
         CsvParse::new(s.trim().as_bytes())
     }
 }
@@ -82,11 +305,6 @@ impl TryFrom<&Path> for CsvParse<File> {
     type Error = TransErr;
 
     fn try_from(p: &Path) -> Result<CsvParse<File>, TransErr> {
-        let file = std::fs::File::open(p).map_err(|e| TransErr::InputFile {
-            source: e,
-            path: p.to_path_buf(),
-        })?;
-
-        CsvParse::new(file)
+        CsvParseBuilder::new().from_path(p)
     }
 }
@@ -0,0 +1,123 @@
+use crate::{import::*, *};
+
+/// Receives the errors of [`Bank::process_into`] as they happen, so they don't have to be kept in
+/// memory until the whole input has been processed.
+//
+pub trait ErrorSink {
+    /// Report one error.
+    //
+    fn report(&mut self, err: TransErr);
+}
+
+/// Collect all errors. This is what [`Bank::process`] uses for [`ProcessReport::errors`].
+//
+impl ErrorSink for Vec<TransErr> {
+    fn report(&mut self, err: TransErr) {
+        self.push(err);
+    }
+}
+
+impl<S: ErrorSink + ?Sized> ErrorSink for &mut S {
+    fn report(&mut self, err: TransErr) {
+        (**self).report(err);
+    }
+}
+
+/// Write each error to a writer as soon as it happens, eg. `std::io::stderr()` or a file. Errors
+/// are formatted with their `Display` implementation, or one per line with
+/// [`TransErr::single_line`], see [`WriteSink::set_single_line`].
+///
+/// Writing stops at the first I/O error, which is returned by [`WriteSink::finish`].
+//
+#[derive(Debug)]
+//
+pub struct WriteSink<W: io::Write> {
+    out: W,
+    count: usize,
+    single_line: bool,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> WriteSink<W> {
+    /// Write errors to `out`. Consider wrapping files in a `BufWriter`.
+    //
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            count: 0,
+            single_line: false,
+            error: None,
+        }
+    }
+
+    /// Write each error on a single line with [`TransErr::single_line`], which is easier to
+    /// process with line based tools. Defaults to `false`.
+    //
+    pub fn set_single_line(&mut self, single_line: bool) -> &mut Self {
+        self.single_line = single_line;
+        self
+    }
+
+    /// The number of errors reported so far, whether writing them succeeded or not.
+    //
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Flush the writer and return it, or the first I/O error that happened while writing.
+    //
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+}
+
+impl<W: io::Write> ErrorSink for WriteSink<W> {
+    fn report(&mut self, err: TransErr) {
+        self.count += 1;
+
+        if self.error.is_none() {
+            let written = match self.single_line {
+                true => writeln!(self.out, "{}", err.single_line()),
+                false => write!(self.out, "{err}"),
+            };
+
+            if let Err(e) = written {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Only count the errors and drop them.
+//
+#[derive(Debug, Clone, Copy, Default)]
+//
+pub struct CountSink {
+    count: usize,
+}
+
+impl CountSink {
+    /// Create a sink with a count of zero.
+    //
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of errors reported so far.
+    //
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl ErrorSink for CountSink {
+    fn report(&mut self, _err: TransErr) {
+        self.count += 1;
+    }
+}
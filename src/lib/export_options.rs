@@ -0,0 +1,215 @@
+use crate::{AccountStore, Amount, Client, Rounding};
+
+/// The order of the clients in an export, see [`ExportOptions::sort_by`]. Clients that compare
+/// equal are ordered by id, so the order is always the same for the same clients.
+//
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+//
+pub enum SortBy {
+    /// By client id.
+    //
+    #[default]
+    Client,
+
+    /// By total funds.
+    //
+    Total,
+
+    /// Unlocked clients before locked ones.
+    //
+    Locked,
+}
+
+/// A column of the client export.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//
+pub enum ClientColumn {
+    /// The client id.
+    //
+    Client,
+
+    /// The available funds.
+    //
+    Available,
+
+    /// The funds held by disputes.
+    //
+    Held,
+
+    /// Available plus held.
+    //
+    Total,
+
+    /// Whether the account is locked.
+    //
+    Locked,
+}
+
+impl ClientColumn {
+    /// All columns, in the default order.
+    //
+    pub const ALL: [ClientColumn; 5] = [
+        ClientColumn::Client,
+        ClientColumn::Available,
+        ClientColumn::Held,
+        ClientColumn::Total,
+        ClientColumn::Locked,
+    ];
+
+    /// The name of the column in the header.
+    //
+    pub fn name(self) -> &'static str {
+        match self {
+            ClientColumn::Client => "client",
+            ClientColumn::Available => "available",
+            ClientColumn::Held => "held",
+            ClientColumn::Total => "total",
+            ClientColumn::Locked => "locked",
+        }
+    }
+}
+
+/// Options for exporting the client data with [`CsvExport`](crate::CsvExport) or
+/// [`JsonExport`](crate::JsonExport).
+///
+/// The defaults are those of [`CsvExport::export`](crate::CsvExport::export): all columns, sorted
+/// by client id, padded to line up and amounts without trailing zeros.
+///
+/// ```
+/// use libtransact::*;
+///
+/// # #[cfg(feature = "csv")]
+/// # fn main() -> Result<(), TransErr> {
+/// let mut bank = Bank::new();
+/// bank.process(CsvParse::try_from("type, client, tx, amount\ndeposit, 1, 1, 2.125")?);
+///
+/// let mut options = ExportOptions::new();
+///
+/// options
+///     .columns([ClientColumn::Client, ClientColumn::Total])
+///     .padded(false)
+///     .decimals(Some(2))
+///     .rounding(Rounding::HalfUp);
+///
+/// assert_eq!(CsvExport::export_with(bank.clients(), &options)?, "client,total\n1,2.13\n");
+/// # Ok(())
+/// # }
+/// #
+/// # #[cfg(not(feature = "csv"))]
+/// # fn main() {}
+/// ```
+//
+#[derive(Debug, Clone)]
+//
+pub struct ExportOptions {
+    pub(crate) sort_by: SortBy,
+    pub(crate) descending: bool,
+    pub(crate) columns: Vec<ClientColumn>,
+    pub(crate) padded: bool,
+    pub(crate) decimals: Option<u32>,
+    pub(crate) rounding: Rounding,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            sort_by: SortBy::Client,
+            descending: false,
+            columns: ClientColumn::ALL.to_vec(),
+            padded: true,
+            decimals: None,
+            rounding: Rounding::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    /// The default options.
+    //
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The order of the clients. Defaults to [`SortBy::Client`].
+    //
+    pub fn sort_by(&mut self, sort_by: SortBy) -> &mut Self {
+        self.sort_by = sort_by;
+        self
+    }
+
+    /// Reverse the order of the clients. Defaults to `false`.
+    //
+    pub fn descending(&mut self, descending: bool) -> &mut Self {
+        self.descending = descending;
+        self
+    }
+
+    /// Which columns to export, in this order. Defaults to [`ClientColumn::ALL`].
+    //
+    pub fn columns(&mut self, columns: impl IntoIterator<Item = ClientColumn>) -> &mut Self {
+        self.columns = columns.into_iter().collect();
+        self
+    }
+
+    /// Whether to pad csv values with spaces so the columns line up. Otherwise there is no
+    /// whitespace between values. Defaults to `true`.
+    //
+    pub fn padded(&mut self, padded: bool) -> &mut Self {
+        self.padded = padded;
+        self
+    }
+
+    /// Show amounts with this number of decimal places, rounded with [`ExportOptions::rounding`].
+    /// Defaults to `None`, which shows amounts as precise as they are without trailing zeros.
+    //
+    pub fn decimals(&mut self, decimals: Option<u32>) -> &mut Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// How to round amounts with more than [`ExportOptions::decimals`] decimal places. Defaults
+    /// to [`Rounding::HalfEven`].
+    //
+    pub fn rounding(&mut self, rounding: Rounding) -> &mut Self {
+        self.rounding = rounding;
+        self
+    }
+
+    // The clients in the configured order.
+    //
+    pub(crate) fn sorted<'a>(&self, clients: &'a (impl AccountStore + ?Sized)) -> Vec<&'a Client> {
+        let mut sorted: Vec<&Client> = AccountStore::iter(clients).collect();
+
+        match self.sort_by {
+            SortBy::Client => sorted.sort_by_key(|c| c.id()),
+            SortBy::Total => sorted.sort_by_key(|c| (c.total(), c.id())),
+            SortBy::Locked => sorted.sort_by_key(|c| (c.is_locked(), c.id())),
+        }
+
+        if self.descending {
+            sorted.reverse();
+        }
+
+        sorted
+    }
+
+    // The value of a column for a client.
+    //
+    pub(crate) fn value(&self, client: &Client, column: ClientColumn) -> String {
+        match column {
+            ClientColumn::Client => client.id().to_string(),
+            ClientColumn::Available => self.amount(client.available()),
+            ClientColumn::Held => self.amount(client.held()),
+            ClientColumn::Total => self.amount(client.total()),
+            ClientColumn::Locked => client.is_locked().to_string(),
+        }
+    }
+
+    fn amount(&self, amount: Amount) -> String {
+        match self.decimals {
+            Some(decimals) => amount.fixed(decimals, self.rounding).to_string(),
+            None => amount.to_string(),
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use crate::{codec::*, import::*, *};
+use std::io::{Read as _, Seek as _, Write as _};
+
+/// A [`TransactionStore`] backed by an append-only log file.
+///
+/// Every stored transaction and every later state transition (eg. Success → Disputed → ChargedBack)
+/// is appended to the log as a separate record. Only a small index (transaction id → file offset and
+/// current state) and the amounts held by open disputes are kept in memory. The transactions
+/// themselves are read back from disk on demand.
+///
+/// The index is rebuilt from the log when the store is opened. Each record is written with a single
+/// write and protected by a length prefix and a checksum, so if the process gets killed mid-write
+/// the torn trailing record is detected and truncated on the next open. Records that were completely
+/// written before the process died are preserved. Call [`FileStore::sync`] if you also need to survive
+/// power loss.
+///
+/// The log starts with a header that identifies it, so opening some other file fails rather than
+/// reading it as records. Layout on disk (all integers little endian):
+///
+/// ```text
+/// magic: "TRANSLOG" | version: u32 | records...
+/// ```
+///
+/// A log written in an older version is read as is. Its header is upgraded to the current version
+/// before the first record that the older version can't hold is appended.
+///
+/// Where each record is:
+///
+/// ```text
+/// len: u32 | payload: [u8; len] | checksum: u32
+/// ```
+//
+#[derive(Debug)]
+//
+pub struct FileStore {
+    file: File,
+    path: PathBuf,
+    index: HashMap<u32, IndexEntry>,
+    held: HashMap<u32, Amount>,
+
+    // The version in the header of the log.
+    //
+    version: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+//
+struct IndexEntry {
+    offset: u64,
+    state: TransState,
+}
+
+// Identifies a transaction log.
+//
+const MAGIC: &[u8; 8] = b"TRANSLOG";
+
+// The version of the log format. Bump when changing the layout and keep reading older versions
+// where possible.
+//
+// - 1: inserts and state changes.
+// - 2: adds disputes with the amount they hold.
+//
+const LOG_VERSION: u32 = 2;
+
+// Size of the magic and the version at the start of the log.
+//
+const HEADER: u64 = 12;
+
+// Record tags.
+//
+const INSERT: u8 = 0;
+const STATE: u8 = 1;
+const DISPUTE: u8 = 2;
+
+// Size of the length prefix and the checksum around each payload.
+//
+const FRAME: u64 = 8;
+
+impl FileStore {
+    /// Open the log at the given path, creating it if it doesn't exist. The index is rebuilt
+    /// by reading the whole log. A torn record at the end of the file is truncated.
+    ///
+    /// Fails with [`TransErr::Store`] if the file is not a transaction log, or one written in a
+    /// newer format.
+    //
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, TransErr> {
+        let path = path.as_ref().to_path_buf();
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(|source| TransErr::Store {
+                source,
+                path: path.clone(),
+            })?;
+
+        let mut store = Self {
+            file,
+            path,
+            index: HashMap::new(),
+            held: HashMap::new(),
+            version: LOG_VERSION,
+        };
+
+        store.header().map_err(|e| store.err(e))?;
+        store.rebuild().map_err(|e| store.err(e))?;
+
+        Ok(store)
+    }
+
+    /// The path of the log file.
+    //
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of transactions in the store.
+    //
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether the store holds no transactions.
+    //
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Flush all written records to the storage device.
+    //
+    pub fn sync(&self) -> Result<(), TransErr> {
+        self.file.sync_data().map_err(|e| self.err(e))
+    }
+
+    fn err(&self, source: io::Error) -> TransErr {
+        TransErr::Store {
+            source,
+            path: self.path.clone(),
+        }
+    }
+
+    // Write the header to a new log, or check the header of an existing one.
+    //
+    fn header(&mut self) -> io::Result<()> {
+        let len = self.file.metadata()?.len();
+
+        let mut header = [0u8; HEADER as usize];
+        header[..8].copy_from_slice(MAGIC);
+        header[8..].copy_from_slice(&LOG_VERSION.to_le_bytes());
+
+        let mut existing = vec![0u8; len.min(HEADER) as usize];
+        (&self.file).rewind()?;
+        (&self.file).read_exact(&mut existing)?;
+
+        // A new log, or one where writing the header got interrupted.
+        //
+        if len < HEADER {
+            if !header.starts_with(&existing) {
+                return Err(corrupt("not a transaction log"));
+            }
+
+            self.file.set_len(0)?;
+            return self.file.write_all(&header);
+        }
+
+        if existing[..8] != MAGIC[..] {
+            return Err(corrupt("not a transaction log"));
+        }
+
+        let version = u32::from_le_bytes([existing[8], existing[9], existing[10], existing[11]]);
+
+        if !(1..=LOG_VERSION).contains(&version) {
+            return Err(corrupt("unsupported transaction log version"));
+        }
+
+        self.version = version;
+        Ok(())
+    }
+
+    // Set the header of an older log to the current version, which reads all older records. Done
+    // before appending a record the older version doesn't have, so if we get killed in between the
+    // log is still valid. The file is opened for appending, so the header needs another handle.
+    //
+    fn upgrade(&mut self) -> io::Result<()> {
+        if self.version == LOG_VERSION {
+            return Ok(());
+        }
+
+        let mut file = std::fs::OpenOptions::new().write(true).open(&self.path)?;
+
+        file.seek(io::SeekFrom::Start(MAGIC.len() as u64))?;
+        file.write_all(&LOG_VERSION.to_le_bytes())?;
+
+        self.version = LOG_VERSION;
+        Ok(())
+    }
+
+    // Read all records, filling the index. A torn record at the end of the file gets truncated.
+    //
+    fn rebuild(&mut self) -> io::Result<()> {
+        let len = self.file.metadata()?.len();
+        let mut reader = io::BufReader::new(&self.file);
+        let mut offset = HEADER;
+
+        reader.seek(io::SeekFrom::Start(HEADER))?;
+
+        while offset < len {
+            let payload = match read_frame(&mut reader, len - offset)? {
+                Some(p) => p,
+
+                // Torn write at the end of the log. Drop it so we can append again.
+                //
+                None => {
+                    self.file.set_len(offset)?;
+                    break;
+                }
+            };
+
+            let mut dec = Decoder::new(&payload);
+
+            match dec.u8()? {
+                INSERT => {
+                    let trans = dec.transact()?;
+
+                    self.index.insert(
+                        trans.id,
+                        IndexEntry {
+                            offset,
+                            state: trans.state,
+                        },
+                    );
+                }
+
+                STATE => {
+                    let id = dec.u32()?;
+                    let state = dec.state()?;
+
+                    match self.index.get_mut(&id) {
+                        Some(entry) => entry.state = state,
+                        None => return Err(corrupt("state change for unknown transaction")),
+                    }
+
+                    self.held.remove(&id);
+                }
+
+                DISPUTE => {
+                    let id = dec.u32()?;
+                    let held = dec.amount()?;
+
+                    match self.index.get_mut(&id) {
+                        Some(entry) => entry.state = TransState::Disputed,
+                        None => return Err(corrupt("dispute of unknown transaction")),
+                    }
+
+                    self.held.insert(id, held);
+                }
+
+                _ => return Err(corrupt("unknown record tag")),
+            }
+
+            offset += FRAME + payload.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    // Append a record. The frame is assembled in memory so it goes out in a single write.
+    //
+    fn append(&mut self, payload: &[u8]) -> io::Result<u64> {
+        let offset = self.file.seek(io::SeekFrom::End(0))?;
+        let mut frame = Vec::with_capacity(payload.len() + FRAME as usize);
+
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&checksum(payload).to_le_bytes());
+
+        self.file.write_all(&frame)?;
+
+        Ok(offset)
+    }
+
+    fn read_at(&self, offset: u64) -> io::Result<Transact> {
+        let mut file = &self.file;
+        file.seek(io::SeekFrom::Start(offset))?;
+
+        let payload =
+            read_frame(&mut file, u64::MAX)?.ok_or_else(|| corrupt("index points past the log"))?;
+        let mut dec = Decoder::new(&payload);
+
+        match dec.u8()? {
+            INSERT => dec.transact(),
+            _ => Err(corrupt("index points to an invalid record")),
+        }
+    }
+}
+
+impl TransactionStore for FileStore {
+    fn contains(&self, id: u32) -> Result<bool, TransErr> {
+        Ok(self.index.contains_key(&id))
+    }
+
+    fn get(&self, id: u32) -> Result<Option<Cow<'_, Transact>>, TransErr> {
+        let entry = match self.index.get(&id) {
+            Some(e) => *e,
+            None => return Ok(None),
+        };
+
+        let mut trans = self.read_at(entry.offset).map_err(|e| self.err(e))?;
+        trans.state = entry.state;
+        trans.held = self.held.get(&id).copied().unwrap_or_default();
+
+        Ok(Some(Cow::Owned(trans)))
+    }
+
+    fn insert(&mut self, trans: Transact) -> Result<(), TransErr> {
+        let mut enc = Encoder::default();
+        enc.u8(INSERT).transact(&trans);
+
+        let offset = self.append(&enc.buf).map_err(|e| self.err(e))?;
+
+        self.index.insert(
+            trans.id,
+            IndexEntry {
+                offset,
+                state: trans.state,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn set_state(&mut self, id: u32, state: TransState) -> Result<(), TransErr> {
+        if !self.index.contains_key(&id) {
+            return Ok(());
+        }
+
+        let mut enc = Encoder::default();
+        enc.u8(STATE).u32(id).state(state);
+
+        self.append(&enc.buf).map_err(|e| self.err(e))?;
+
+        if let Some(entry) = self.index.get_mut(&id) {
+            entry.state = state;
+        }
+
+        self.held.remove(&id);
+        Ok(())
+    }
+
+    fn set_disputed(&mut self, id: u32, held: Amount) -> Result<(), TransErr> {
+        if !self.index.contains_key(&id) {
+            return Ok(());
+        }
+
+        let mut enc = Encoder::default();
+        enc.u8(DISPUTE).u32(id).amount(held);
+
+        self.upgrade().map_err(|e| self.err(e))?;
+        self.append(&enc.buf).map_err(|e| self.err(e))?;
+
+        if let Some(entry) = self.index.get_mut(&id) {
+            entry.state = TransState::Disputed;
+        }
+
+        self.held.insert(id, held);
+        Ok(())
+    }
+
+    fn held(&self, id: u32) -> Result<Option<Amount>, TransErr> {
+        Ok(self.held.get(&id).copied())
+    }
+
+    // Read the log sequentially through a separate file handle, taking the current state from
+    // the index.
+    //
+    fn iter(&self) -> Box<dyn Iterator<Item = Result<Cow<'_, Transact>, TransErr>> + '_> {
+        let open = || -> io::Result<_> {
+            let mut reader = io::BufReader::new(File::open(&self.path)?);
+            reader.seek(io::SeekFrom::Start(HEADER))?;
+            Ok(reader)
+        };
+
+        let mut reader = match open() {
+            Ok(r) => r,
+            Err(e) => return Box::new(std::iter::once(Err(self.err(e)))),
+        };
+
+        let mut done = false;
+
+        Box::new(std::iter::from_fn(move || {
+            while !done {
+                let payload = match read_frame(&mut reader, u64::MAX) {
+                    Ok(Some(p)) => p,
+                    Ok(None) => break,
+                    Err(e) => {
+                        done = true;
+                        return Some(Err(self.err(e)));
+                    }
+                };
+
+                let mut dec = Decoder::new(&payload);
+
+                let trans = match dec.u8() {
+                    Ok(INSERT) => dec.transact(),
+                    Ok(_) => continue,
+                    Err(e) => Err(e),
+                };
+
+                return Some(
+                    trans
+                        .map(|mut t| {
+                            if let Some(entry) = self.index.get(&t.id) {
+                                t.state = entry.state;
+                            }
+                            Cow::Owned(t)
+                        })
+                        .map_err(|e| {
+                            done = true;
+                            self.err(e)
+                        }),
+                );
+            }
+
+            done = true;
+            None
+        }))
+    }
+
+    fn is_durable(&self) -> bool {
+        true
+    }
+}
+
+// Read one frame and verify its checksum. Returns None if the frame is incomplete, or if it is the
+// last frame in the file and the checksum doesn't match. That is what a torn write looks like.
+// `remaining` is the number of bytes left in the file, so we don't try to allocate a bogus length
+// read from a torn prefix.
+//
+fn read_frame(reader: &mut impl io::Read, remaining: u64) -> io::Result<Option<Vec<u8>>> {
+    let mut word = [0u8; 4];
+
+    if !read_full(reader, &mut word)? {
+        return Ok(None);
+    }
+
+    let len = u32::from_le_bytes(word) as u64;
+
+    if len + FRAME > remaining {
+        return Ok(None);
+    }
+
+    let mut payload = vec![0u8; len as usize];
+
+    if !read_full(reader, &mut payload)? || !read_full(reader, &mut word)? {
+        return Ok(None);
+    }
+
+    if u32::from_le_bytes(word) != checksum(&payload) {
+        if len + FRAME == remaining {
+            return Ok(None);
+        }
+
+        return Err(corrupt("checksum mismatch"));
+    }
+
+    Ok(Some(payload))
+}
+
+// Like read_exact, but returns false on a premature end of file.
+//
+fn read_full(reader: &mut impl io::Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// FNV-1a. We only need to detect torn writes, not malicious tampering.
+//
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
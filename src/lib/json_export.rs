@@ -0,0 +1,158 @@
+use crate::{import::*, *};
+use serde::ser::SerializeMap as _;
+use std::io::Write as _;
+
+/// The layout of a JSON export, see [`JsonExport`].
+//
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+//
+pub enum JsonStyle {
+    /// A JSON array with one client object per line.
+    //
+    #[default]
+    Array,
+
+    /// [Newline delimited JSON](https://github.com/ndjson/ndjson-spec): one client object per
+    /// line, without an enclosing array.
+    //
+    Lines,
+}
+
+/// Namespace for exporting the client data to JSON.
+///
+/// Every client is an object with the columns of [`ExportOptions::columns`] as keys, see
+/// [`ClientColumn::name`]. The id is a number and the locked status a boolean. Amounts are
+/// strings, so they keep their precision: `{"client":1,"available":"1.5","held":"0","total":"1.5","locked":false}`.
+///
+/// The [`ExportOptions`] select, order and round like for [`CsvExport`]. There is no padding,
+/// so [`ExportOptions::padded`] is ignored.
+//
+#[derive(Debug, Copy, Clone)]
+//
+pub struct JsonExport {}
+
+// A client with the columns and rounding of the options.
+//
+struct Row<'a> {
+    client: &'a Client,
+    options: &'a ExportOptions,
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (client, options) = (self.client, self.options);
+        let mut map = serializer.serialize_map(Some(options.columns.len()))?;
+
+        for &column in &options.columns {
+            match column {
+                ClientColumn::Client => map.serialize_entry(column.name(), &client.id())?,
+                ClientColumn::Locked => map.serialize_entry(column.name(), &client.is_locked())?,
+
+                ClientColumn::Available | ClientColumn::Held | ClientColumn::Total => {
+                    map.serialize_entry(column.name(), &options.value(client, column))?
+                }
+            }
+        }
+
+        map.end()
+    }
+}
+
+impl JsonExport {
+    /// Export the client data to JSON. This holds the whole output in memory, prefer
+    /// [`JsonExport::write_to`] for large numbers of clients.
+    //
+    pub fn export(
+        clients: &(impl AccountStore + ?Sized),
+        style: JsonStyle,
+    ) -> Result<String, TransErr> {
+        Self::export_with(clients, &ExportOptions::default(), style)
+    }
+
+    /// Export the client data to JSON, formatted as configured by `options`.
+    //
+    pub fn export_with(
+        clients: &(impl AccountStore + ?Sized),
+        options: &ExportOptions,
+        style: JsonStyle,
+    ) -> Result<String, TransErr> {
+        let mut out = Vec::new();
+
+        Self::write_with(clients, options, style, &mut out)?;
+
+        // serde_json only writes valid utf8.
+        //
+        String::from_utf8(out).map_err(|e| TransErr::SerializeClients {
+            source: io::Error::new(io::ErrorKind::InvalidData, e),
+        })
+    }
+
+    /// Export the client data to JSON, writing each client to `out` as it is serialized. The
+    /// output is buffered, so there is no need to pass a buffered writer. Errors writing to `out`
+    /// are returned as [`TransErr::SerializeClients`].
+    //
+    pub fn write_to<W: io::Write>(
+        clients: &(impl AccountStore + ?Sized),
+        style: JsonStyle,
+        out: W,
+    ) -> Result<(), TransErr> {
+        Self::write_with(clients, &ExportOptions::default(), style, out)
+    }
+
+    /// Like [`JsonExport::write_to`], formatted as configured by `options`.
+    //
+    pub fn write_with<W: io::Write>(
+        clients: &(impl AccountStore + ?Sized),
+        options: &ExportOptions,
+        style: JsonStyle,
+        out: W,
+    ) -> Result<(), TransErr> {
+        let rows = options
+            .sorted(clients)
+            .into_iter()
+            .map(|client| Ok(Row { client, options }));
+
+        write_json(rows, style, out)
+    }
+}
+
+// Write the rows as an array or one per line, see [`JsonStyle`]. Errors producing a row are
+// returned as is.
+//
+pub(crate) fn write_json<W: io::Write>(
+    rows: impl Iterator<Item = Result<impl Serialize, TransErr>>,
+    style: JsonStyle,
+    out: W,
+) -> Result<(), TransErr> {
+    let err = |source| TransErr::SerializeClients { source };
+
+    let mut out = io::BufWriter::new(out);
+    let mut empty = true;
+
+    if style == JsonStyle::Array {
+        writeln!(out, "[").map_err(err)?;
+    }
+
+    for row in rows {
+        if style == JsonStyle::Array && !empty {
+            writeln!(out, ",").map_err(err)?;
+        }
+
+        serde_json::to_writer(&mut out, &row?).map_err(|e| err(e.into()))?;
+        empty = false;
+
+        if style == JsonStyle::Lines {
+            writeln!(out).map_err(err)?;
+        }
+    }
+
+    if style == JsonStyle::Array {
+        match empty {
+            true => writeln!(out, "]"),
+            false => writeln!(out, "\n]"),
+        }
+        .map_err(err)?;
+    }
+
+    out.flush().map_err(err)
+}
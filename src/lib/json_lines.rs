@@ -0,0 +1,254 @@
+use crate::{
+    import::*,
+    position::{position, with_position},
+    transaction::*,
+    TransErr,
+};
+use serde_json::value::RawValue;
+use std::io::BufRead as _;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A [JSON Lines](https://jsonlines.org) source for transactions. Every line holds one object
+/// with the same fields as the columns of the csv format:
+///
+/// ```json
+/// {"type": "deposit",    "client": 1, "tx": 1, "amount": "1.0"}
+/// {"type": "withdrawal", "client": 1, "tx": 2, "amount": 0.5}
+/// {"type": "dispute",    "client": 1, "tx": 1}
+/// ```
+///
+/// The amount can be a string or a number and is taken as written, so `0.1` is exactly one tenth.
+/// It can be left out or be `null` for transactions without an amount. Other fields are ignored.
+///
+/// Unlocks are only read after [`JsonLinesParse::set_admin`], with the reason in a `reason` field.
+///
+/// The transactions are validated like those of [`CsvParse`](crate::CsvParse), so the same
+/// input yields the same transactions and errors. A line that is not a valid object is reported
+/// as a [`TransErr::DeserializeCsv`] and skipped. Blank lines and a UTF-8 byte order mark at the
+/// start are ignored. Every transaction and every error for a line carries the
+/// [`Position`](crate::Position) of that line, with the line as row.
+///
+/// For untrusted input, limit the length of lines with [`JsonLinesParse::set_max_line_len`].
+//
+pub struct JsonLinesParse<R> {
+    reader: io::BufReader<R>,
+    buf: Vec<u8>,
+
+    // Position of the next line.
+    //
+    line: u64,
+    byte: u64,
+
+    // Set after an I/O error or a line that is too long, so we don't keep retrying.
+    //
+    done: bool,
+    admin: bool,
+    max_line_len: Option<usize>,
+}
+
+// A line of input. Borrows the amount as written, so numbers aren't rounded to a float.
+//
+#[derive(Deserialize)]
+//
+struct JsonRecord<'a> {
+    #[serde(borrow, rename = "type")]
+    ttype: Cow<'a, str>,
+    client: u16,
+    tx: u32,
+
+    #[serde(borrow, default)]
+    amount: Option<&'a RawValue>,
+
+    #[serde(borrow, default)]
+    reason: Option<Cow<'a, str>>,
+}
+
+impl<R: io::Read> JsonLinesParse<R> {
+    /// Create a new source for JSON Lines data.
+    //
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: io::BufReader::new(reader),
+            buf: Vec::new(),
+            line: 1,
+            byte: 0,
+            done: false,
+            admin: false,
+            max_line_len: None,
+        }
+    }
+
+    /// Whether the input is privileged and may hold unlocks, like
+    /// [`CsvParseBuilder::admin`](crate::CsvParseBuilder::admin). Defaults to `false`.
+    //
+    pub fn set_admin(&mut self, admin: bool) -> &mut Self {
+        self.admin = admin;
+        self
+    }
+
+    /// The maximum length of a line in bytes, not counting the line ending, like
+    /// [`CsvParseBuilder::max_row_len`](crate::CsvParseBuilder::max_row_len). A longer line is
+    /// reported as a [`TransErr::DeserializeCsv`] and ends the input, so a malicious file can't
+    /// make us buffer an arbitrary amount of data. Defaults to `None`, which means no limit.
+    //
+    pub fn set_max_line_len(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_line_len = max;
+        self
+    }
+
+    // The next line that isn't blank, with its position. None at the end of the input.
+    //
+    fn read_line(&mut self) -> io::Result<Option<(u64, u64)>> {
+        loop {
+            self.buf.clear();
+
+            let (line, mut byte) = (self.line, self.byte);
+
+            // Read at most one byte more than allowed, besides the line ending and the byte order
+            // mark, so a line that is too long is not buffered whole.
+            //
+            let limit = match self.max_line_len {
+                Some(max) if byte == 0 => max as u64 + 2 + BOM.len() as u64,
+                Some(max) => max as u64 + 2,
+                None => u64::MAX,
+            };
+
+            let n = io::Read::take(&mut self.reader, limit).read_until(b'\n', &mut self.buf)?;
+
+            if n == 0 {
+                return Ok(None);
+            }
+
+            if byte == 0 && self.buf.starts_with(BOM) {
+                self.buf.drain(..BOM.len());
+                byte += BOM.len() as u64;
+            }
+
+            // A line that is too long ends the input, its position is where it starts.
+            //
+            if let Some(max) = self.max_line_len {
+                let text = match self.buf.strip_suffix(b"\n") {
+                    Some(text) => text.strip_suffix(b"\r").unwrap_or(text),
+                    None => &self.buf,
+                };
+
+                if text.len() > max {
+                    self.byte = byte;
+
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line is longer than the maximum of {max} bytes"),
+                    ));
+                }
+            }
+
+            self.line += 1;
+            self.byte += n as u64;
+
+            if !self.buf.trim_ascii().is_empty() {
+                return Ok(Some((line, byte)));
+            }
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for JsonLinesParse<R> {
+    type Item = Result<Transact, TransErr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let (line, byte) = match self.read_line() {
+            Ok(Some(start)) => start,
+            Ok(None) => return None,
+
+            Err(e) => {
+                self.done = true;
+
+                let pos = position(self.line, self.byte, Vec::new());
+                let err = TransErr::DeserializeCsv {
+                    source: e.into(),
+                    pos: None,
+                };
+
+                return Some(with_position(Err(err), pos));
+            }
+        };
+
+        let row = self.buf.trim_ascii_end();
+        let pos = position(line, byte, row.to_vec());
+        let text = row.trim_ascii_start();
+
+        Some(with_position(transact(text, self.admin), pos))
+    }
+}
+
+fn transact(text: &[u8], admin: bool) -> Result<Transact, TransErr> {
+    let invalid = |e: serde_json::Error| TransErr::DeserializeCsv {
+        source: io::Error::from(e).into(),
+        pos: None,
+    };
+
+    // Serde would also accept the fields as an array.
+    //
+    if !text.starts_with(b"{") {
+        return Err(TransErr::DeserializeCsv {
+            source: io::Error::new(io::ErrorKind::InvalidData, "expected a JSON object").into(),
+            pos: None,
+        });
+    }
+
+    let record: JsonRecord<'_> = serde_json::from_slice(text).map_err(invalid)?;
+
+    // A string is unquoted and, like a csv field, trimmed and missing when empty. Anything else is
+    // passed on as written and rejected there if it isn't a number.
+    //
+    let amount = match record.amount.map(RawValue::get) {
+        None => None,
+
+        Some(raw) if raw.starts_with('"') => {
+            let s: String = serde_json::from_str(raw).map_err(invalid)?;
+            let s = s.trim();
+
+            (!s.is_empty()).then(|| Cow::Owned(s.to_string()))
+        }
+
+        Some(raw) => Some(Cow::Borrowed(raw)),
+    };
+
+    let mut fields = CsvRecord::new(trim(record.ttype), record.client, record.tx, amount);
+    fields.reason = record.reason.map(trim);
+
+    Transact::from_record(fields, admin)
+}
+
+// Trim a string field like a csv field.
+//
+fn trim(s: Cow<'_, str>) -> Cow<'_, str> {
+    match s {
+        Cow::Borrowed(s) => Cow::Borrowed(s.trim()),
+        Cow::Owned(s) => Cow::Owned(s.trim().to_string()),
+    }
+}
+
+impl<R> fmt::Debug for JsonLinesParse<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JsonLinesParse")
+    }
+}
+
+impl TryFrom<&Path> for JsonLinesParse<File> {
+    type Error = TransErr;
+
+    fn try_from(p: &Path) -> Result<JsonLinesParse<File>, TransErr> {
+        let file = File::open(p).map_err(|e| TransErr::InputFile {
+            source: e,
+            path: p.to_path_buf(),
+        })?;
+
+        Ok(JsonLinesParse::new(file))
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{import::*, json_export::write_json, *};
+use serde::ser::SerializeMap as _;
+use std::io::Write as _;
+
+/// Namespace for exporting the stored transactions, eg. [`Bank::transactions`], with their final
+/// state. Lets reconciliation see which deposits are still disputed or were charged back.
+///
+/// Every transaction has the columns of the input, followed by its state: `type`, `client`, `tx`,
+/// `amount`, `reason` and `state`, where the reason is only set for unlocks and the state is one
+/// of [`TransState::as_str`]. The transactions are ordered by id. Only their ids are collected
+/// before anything is written, each transaction is read from the store when its turn comes.
+///
+/// ```
+/// use libtransact::*;
+///
+/// # #[cfg(feature = "csv")]
+/// # fn main() -> Result<(), TransErr> {
+/// let mut bank = Bank::new();
+///
+/// bank.process(CsvParse::try_from(
+///     "type, client, tx, amount\ndeposit, 1, 1, 2.5\ndeposit, 1, 2, 1\ndispute, 1, 1,",
+/// )?);
+///
+/// let mut out = Vec::new();
+/// LedgerExport::write_csv(bank.transactions(), &mut out)?;
+///
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "type,client,tx,amount,reason,state\ndeposit,1,1,2.5,,disputed\ndeposit,1,2,1,,success\n"
+/// );
+/// # Ok(())
+/// # }
+/// #
+/// # #[cfg(not(feature = "csv"))]
+/// # fn main() {}
+/// ```
+//
+#[derive(Debug, Copy, Clone)]
+//
+pub struct LedgerExport {}
+
+// A transaction as an object with the columns of the ledger.
+//
+struct Row<'a>(Cow<'a, Transact>);
+
+impl Serialize for Row<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let t = &self.0;
+        let mut map = serializer.serialize_map(Some(6))?;
+
+        map.serialize_entry("type", t.ttype.name())?;
+        map.serialize_entry("client", &t.client)?;
+        map.serialize_entry("tx", &t.id)?;
+        map.serialize_entry("amount", &t.ttype.amount())?;
+        map.serialize_entry("reason", &t.ttype.reason())?;
+        map.serialize_entry("state", &t.state)?;
+
+        map.end()
+    }
+}
+
+impl LedgerExport {
+    /// Export the transactions in `store` to CSV. Errors reading the store are returned as is,
+    /// errors writing to `out` as [`TransErr::SerializeClients`]. The output is buffered.
+    //
+    pub fn write_csv<S, W>(store: &S, out: W) -> Result<(), TransErr>
+    where
+        S: TransactionStore + ?Sized,
+        W: io::Write,
+    {
+        let err = |source| TransErr::SerializeClients { source };
+
+        let transactions = sorted(store)?;
+        let mut out = io::BufWriter::new(out);
+
+        writeln!(out, "type,client,tx,amount,reason,state").map_err(err)?;
+
+        for t in transactions {
+            let t = t?;
+            let amount = t.ttype.amount().map(|a| a.to_string()).unwrap_or_default();
+
+            writeln!(
+                out,
+                "{},{},{},{amount},{},{}",
+                t.ttype.name(),
+                t.client,
+                t.id,
+                quoted(t.ttype.reason().unwrap_or_default()),
+                t.state
+            )
+            .map_err(err)?;
+        }
+
+        out.flush().map_err(err)
+    }
+
+    /// Export the transactions in `store` to JSON, as an array or one object per line. Amounts
+    /// are strings, like in [`JsonExport`], and `null` for transactions without an amount.
+    /// Errors are returned like for [`LedgerExport::write_csv`].
+    //
+    pub fn write_json<S, W>(store: &S, style: JsonStyle, out: W) -> Result<(), TransErr>
+    where
+        S: TransactionStore + ?Sized,
+        W: io::Write,
+    {
+        let transactions = sorted(store)?;
+
+        let rows = transactions.map(|t| t.map(Row));
+
+        write_json(rows, style, out)
+    }
+}
+
+// The transactions in the store, ordered by id. Only the ids are sorted, each transaction is read
+// with `get` as the iterator reaches it.
+//
+fn sorted<S: TransactionStore + ?Sized>(
+    store: &S,
+) -> Result<impl Iterator<Item = Result<Cow<'_, Transact>, TransErr>>, TransErr> {
+    let mut ids = store
+        .iter()
+        .map(|t| t.map(|t| t.id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    ids.sort_unstable();
+
+    Ok(ids.into_iter().filter_map(|id| store.get(id).transpose()))
+}
+
+// Quote a csv field that holds a comma, a quote or a line ending.
+//
+fn quoted(field: &str) -> Cow<'_, str> {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => Cow::Owned(format!("\"{}\"", field.replace('"', "\"\""))),
+        false => Cow::Borrowed(field),
+    }
+}
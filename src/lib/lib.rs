@@ -19,17 +19,64 @@
     variant_size_differences
 )]
 
+mod amount;
 mod bank;
 mod client;
+mod codec;
+#[cfg(any(feature = "csv", feature = "safe_csv"))]
+mod column_map;
+#[cfg(feature = "csv")]
+mod csv_builder;
 mod csv_export;
+mod csv_layout;
+#[cfg(feature = "csv")]
 mod csv_parse;
+mod error_sink;
+mod export_options;
+mod file_store;
+mod json_export;
+mod json_lines;
+mod ledger_export;
+mod owned_err;
+mod position;
+#[cfg(feature = "csv")]
+mod reject_sink;
+mod report;
+mod row_error;
+#[cfg(feature = "csv")]
+mod row_scan;
+#[cfg(feature = "safe_csv")]
+mod safe_csv;
+mod snapshot;
+mod store;
 mod trans_err;
 mod transaction;
 
+pub use amount::*;
 pub use bank::*;
 pub use client::*;
+#[cfg(feature = "csv")]
+pub use csv_builder::*;
 pub use csv_export::*;
+pub use csv_layout::*;
+#[cfg(feature = "csv")]
 pub use csv_parse::*;
+pub use error_sink::*;
+pub use export_options::*;
+pub use file_store::*;
+pub use json_export::*;
+pub use json_lines::*;
+pub use ledger_export::*;
+pub use owned_err::*;
+pub use position::*;
+#[cfg(feature = "csv")]
+pub use reject_sink::*;
+pub use report::*;
+pub use row_error::*;
+#[cfg(feature = "safe_csv")]
+pub use safe_csv::*;
+pub use snapshot::*;
+pub use store::*;
 pub use trans_err::*;
 pub use transaction::*;
 
@@ -37,15 +84,15 @@ pub use transaction::*;
 //
 mod import {
     pub(crate) use {
-        bigdecimal::{BigDecimal, Signed},
-        serde::Deserialize,
+        serde::{Deserialize, Serialize},
         std::{
             borrow::Cow,
-            collections::HashMap,
+            collections::{HashMap, HashSet},
             fmt,
-            fmt::Write,
             fs::File,
+            io,
             path::{Path, PathBuf},
+            str::FromStr,
         },
     };
 }
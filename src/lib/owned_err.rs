@@ -0,0 +1,79 @@
+use crate::{import::*, *};
+
+/// An owned snapshot of a [`TransErr`], with the source chain captured as text.
+///
+/// [`TransErr`] wraps `std::io::Error` and [`RowError`], which makes it neither `Clone` nor
+/// `UnwindSafe`. This type only holds plain data, so it is `Clone + Send + Sync + UnwindSafe`, and
+/// it can be serialized, eg. to ship errors to another thread or into a JSON report.
+///
+/// ```
+/// use libtransact::*;
+///
+/// let err = OwnedErr::from(&TransErr::NoHeader);
+///
+/// assert_eq!(err.kind, TransErrKind::NoHeader);
+/// assert!(err.sources.is_empty());
+/// ```
+//
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+//
+pub struct OwnedErr {
+    /// The variant of the original error.
+    //
+    pub kind: TransErrKind,
+
+    /// The stable code of the error, see [`TransErrKind::code`].
+    //
+    pub code: &'static str,
+
+    /// The category of the error.
+    //
+    pub category: ErrorCategory,
+
+    /// The error message, as displayed by the original error.
+    //
+    pub message: String,
+
+    /// Where in the input the offending row was, if known.
+    //
+    pub position: Option<Position>,
+
+    /// The messages of the chain of underlying errors, starting with the direct source.
+    //
+    pub sources: Vec<String>,
+}
+
+impl From<&TransErr> for OwnedErr {
+    fn from(err: &TransErr) -> Self {
+        let mut sources = Vec::new();
+        let mut source = std::error::Error::source(err);
+
+        while let Some(s) = source {
+            sources.push(s.to_string());
+            source = s.source();
+        }
+
+        Self {
+            kind: err.kind(),
+            code: err.code(),
+            category: err.category(),
+            message: err.to_string().trim().to_string(),
+            position: err.position().cloned(),
+            sources,
+        }
+    }
+}
+
+impl From<TransErr> for OwnedErr {
+    fn from(err: TransErr) -> Self {
+        Self::from(&err)
+    }
+}
+
+impl fmt::Display for OwnedErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n{}", self.message)
+    }
+}
+
+impl std::error::Error for OwnedErr {}
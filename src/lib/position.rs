@@ -0,0 +1,70 @@
+use crate::{import::*, TransErr, Transact};
+
+/// Where in the input a transaction came from, so errors can point to the offending row.
+//
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
+//
+pub struct Position {
+    /// The line on which the row starts, starting at 1.
+    //
+    pub line: u64,
+
+    /// The byte offset at which the row starts.
+    //
+    pub byte: u64,
+
+    /// The row exactly as it was read from the input, quotes and whitespace included, without the
+    /// line ending. Empty when the row could not be read, eg. when it is too long.
+    //
+    #[serde(serialize_with = "lossy")]
+    pub row: Vec<u8>,
+}
+
+impl Position {
+    /// The row as text. Invalid UTF-8 is replaced with `U+FFFD`.
+    //
+    pub fn row_text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.row)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} (byte {}): \"{}\"",
+            self.line,
+            self.byte,
+            self.row_text()
+        )
+    }
+}
+
+fn lossy<S: serde::Serializer>(row: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&String::from_utf8_lossy(row))
+}
+
+// The position of a record, with the raw bytes of the row.
+//
+pub(crate) fn position(line: u64, byte: u64, row: Vec<u8>) -> Box<Position> {
+    Box::new(Position { line, byte, row })
+}
+
+// Attach the position of the row to the transaction or the error.
+//
+pub(crate) fn with_position(
+    result: Result<Transact, TransErr>,
+    pos: Box<Position>,
+) -> Result<Transact, TransErr> {
+    match result {
+        Ok(mut trans) => {
+            trans.pos = Some(pos);
+            Ok(trans)
+        }
+
+        Err(mut e) => {
+            e.set_position(Some(pos));
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{import::*, *};
+
+/// Write the rejected input rows to a CSV file that can be sent back to the partner, corrected and
+/// resubmitted. Every error is passed on to another sink afterwards, eg. a [`WriteSink`] to also
+/// log them, or a [`CountSink`].
+///
+/// The columns are those of the input, as described by a [`RejectLayout`], followed by
+/// `error_code` (see [`TransErrKind::code`]), `line` and `message`. This covers rows the parser
+/// rejected as well as transactions the bank rejected. Errors that are not about a row of input,
+/// like failing to access the transaction store, are only passed on.
+///
+/// With the layout of the parser, see [`CsvParse::reject_layout`], every row is written with the
+/// fields as they were read, in the same order, delimiter and quoting as the input:
+///
+/// ```
+/// use libtransact::*;
+///
+/// let input = "client, type, tx, amount\n1, deposit, 1, -5\n1, withdrawal, 2, 1.0";
+/// let parser = CsvParse::try_from(input)?;
+///
+/// let mut sink = RejectSink::with_layout(Vec::new(), parser.reject_layout(), CountSink::new());
+/// Bank::new().process_into(parser, &mut sink);
+///
+/// let (rejects, count) = sink.finish().unwrap();
+/// let rejects = String::from_utf8(rejects).unwrap();
+///
+/// assert_eq!(count.count(), 2);
+/// assert!(rejects.starts_with("client,type,tx,amount,error_code,line,message\n"));
+/// assert!(rejects.contains("1,deposit,1,-5,E_INVALID_TX,2,"));
+/// assert!(rejects.contains("1,withdrawal,2,1.0,E_NO_CLIENT,3,"));
+/// # Ok::<(), TransErr>(())
+/// ```
+//
+pub struct RejectSink<W: io::Write, S: ErrorSink> {
+    out: csv::Writer<W>,
+    layout: RejectLayout,
+    next: S,
+    count: usize,
+    error: Option<csv::Error>,
+}
+
+/// The columns and dialect of the input, so [`RejectSink`] can write rejected rows the way they
+/// were read.
+///
+/// For csv input, get it from the parser with [`CsvParse::reject_layout`]. The raw row of each
+/// error is split again with the dialect of the input and written unchanged. A row that doesn't
+/// have as many fields as there are columns is written whole in the first column, so nothing is
+/// lost.
+///
+/// The default layout has the columns `type`, `client`, `tx` and `amount`, filled in from the
+/// parsed transaction. It is meant for input that isn't csv, like [`JsonLinesParse`]. Rows that
+/// could not be parsed at all are written whole in the first column.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+//
+pub struct RejectLayout {
+    pub(crate) columns: Vec<String>,
+    pub(crate) dialect: Option<Dialect>,
+}
+
+// How csv input splits rows into fields.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//
+pub(crate) struct Dialect {
+    pub(crate) delimiter: u8,
+    pub(crate) quote: u8,
+    pub(crate) quoting: bool,
+}
+
+impl Default for RejectLayout {
+    fn default() -> Self {
+        Self {
+            columns: ["type", "client", "tx", "amount"]
+                .map(String::from)
+                .to_vec(),
+            dialect: None,
+        }
+    }
+}
+
+impl RejectLayout {
+    /// The default layout, for input that isn't csv.
+    //
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Comma delimited csv input with the given columns, eg. for `SafeCsvParse`.
+    //
+    pub fn csv<S: Into<String>>(columns: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            columns: columns.into_iter().map(Into::into).collect(),
+
+            dialect: Some(Dialect {
+                delimiter: b',',
+                quote: b'"',
+                quoting: true,
+            }),
+        }
+    }
+
+    /// The names of the columns, as in the header of the input.
+    //
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    // Split a raw row of the input into its fields, if it has one for every column.
+    //
+    fn split(&self, row: &[u8]) -> Option<Vec<String>> {
+        let dialect = self.dialect?;
+
+        let record = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .delimiter(dialect.delimiter)
+            .quote(dialect.quote)
+            .quoting(dialect.quoting)
+            .from_reader(row)
+            .into_records()
+            .next()?
+            .ok()?;
+
+        match record.len() == self.columns.len() {
+            true => Some(record.iter().map(String::from).collect()),
+            false => None,
+        }
+    }
+
+    // Fill in the columns from a parsed transaction.
+    //
+    fn transact_fields(&self, t: &Transact) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|name| match Column::from_name(name) {
+                Column::Type => t.ttype.name().to_string(),
+                Column::Client => t.client.to_string(),
+                Column::Tx => t.id.to_string(),
+                Column::Amount => t.ttype.amount().map(|a| a.to_string()).unwrap_or_default(),
+                Column::Reason => t.ttype.reason().unwrap_or_default().to_string(),
+
+                Column::Extra(name) => t
+                    .extra
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    // Fill in the columns from a record that is not a valid transaction.
+    //
+    fn record_fields(&self, r: &CsvRecord<'_>) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|name| match Column::from_name(name) {
+                Column::Type => r.r#type.to_string(),
+                Column::Client => r.client.to_string(),
+                Column::Tx => r.tx.to_string(),
+                Column::Amount => r.amount.as_deref().unwrap_or_default().to_string(),
+                Column::Reason => r.reason.as_deref().unwrap_or_default().to_string(),
+                Column::Extra(_) => String::new(),
+            })
+            .collect()
+    }
+
+    // The input columns of the row an error is about, or None if it isn't about a row.
+    //
+    fn fields(&self, err: &TransErr) -> Option<Vec<String>> {
+        let row = err.position().map(|p| p.row.as_slice()).unwrap_or_default();
+
+        let about_row = err.transact().is_some()
+            || matches!(
+                err,
+                TransErr::DeserializeCsv { .. } | TransErr::DeserializeTransact { .. }
+            );
+
+        if !about_row {
+            return None;
+        }
+
+        if let Some(fields) = self.split(row) {
+            return Some(fields);
+        }
+
+        match err {
+            TransErr::DeserializeTransact { record, .. } => Some(self.record_fields(record)),
+
+            // Keep a row that can't be split whole.
+            //
+            TransErr::DeserializeCsv { .. } => {
+                let mut fields = vec![String::new(); self.columns.len()];
+
+                if let Some(first) = fields.first_mut() {
+                    *first = String::from_utf8_lossy(row).into_owned();
+                }
+
+                Some(fields)
+            }
+
+            _ => err.transact().map(|t| self.transact_fields(t)),
+        }
+    }
+}
+
+impl<W: io::Write, S: ErrorSink> RejectSink<W, S> {
+    /// Write rejected rows to `out` with the default [`RejectLayout`]. See
+    /// [`RejectSink::with_layout`].
+    //
+    pub fn new(out: W, next: S) -> Self {
+        Self::with_layout(out, &RejectLayout::default(), next)
+    }
+
+    /// Write rejected rows to `out`, which is buffered, in the columns of `layout` and pass every
+    /// error on to `next`. The header is written right away, so the file shows there were no
+    /// rejects rather than being empty.
+    //
+    pub fn with_layout(out: W, layout: &RejectLayout, next: S) -> Self {
+        let mut builder = csv::WriterBuilder::new();
+
+        if let Some(dialect) = layout.dialect {
+            builder.delimiter(dialect.delimiter).quote(dialect.quote);
+        }
+
+        let mut out = builder.from_writer(out);
+
+        let header =
+            layout
+                .columns
+                .iter()
+                .map(String::as_str)
+                .chain(["error_code", "line", "message"]);
+
+        let error = out.write_record(header).err();
+
+        Self {
+            out,
+            layout: layout.clone(),
+            next,
+            count: 0,
+            error,
+        }
+    }
+
+    /// The number of rejected rows reported so far, whether writing them succeeded or not.
+    //
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Flush the writer and return it with the next sink, or the first I/O error that happened
+    /// while writing.
+    //
+    pub fn finish(mut self) -> io::Result<(W, S)> {
+        if let Some(e) = self.error {
+            return Err(e.into());
+        }
+
+        self.out.flush()?;
+
+        match self.out.into_inner() {
+            Ok(out) => Ok((out, self.next)),
+            Err(e) => Err(e.into_error()),
+        }
+    }
+}
+
+impl<W: io::Write, S: ErrorSink> ErrorSink for RejectSink<W, S> {
+    fn report(&mut self, err: TransErr) {
+        if let Some(fields) = self.layout.fields(&err) {
+            self.count += 1;
+
+            if self.error.is_none() {
+                let line = err
+                    .position()
+                    .map(|p| p.line.to_string())
+                    .unwrap_or_default();
+
+                let record =
+                    fields
+                        .into_iter()
+                        .chain([err.code().to_string(), line, err.message()]);
+
+                self.error = self.out.write_record(record).err();
+            }
+        }
+
+        self.next.report(err);
+    }
+}
+
+impl<W: io::Write, S: ErrorSink> fmt::Debug for RejectSink<W, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RejectSink")
+            .field("count", &self.count)
+            .finish()
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{import::*, *};
+use std::time::Duration;
+
+/// Summary of one call to [`Bank::process`]. Only covers the transactions of that batch.
+//
+#[derive(Debug, Default)]
+//
+pub struct ProcessReport {
+    /// The number of transactions that were applied.
+    //
+    pub succeeded: usize,
+
+    /// The number of transactions that were rejected, including rows that could not be parsed.
+    //
+    pub failed: usize,
+
+    /// The number of rejected transactions by kind of error, eg. to alert on a spike of
+    /// [`TransErrKind::InsufficientFunds`]. Kinds that didn't occur are left out. Unlike
+    /// [`ProcessReport::errors`] this is always filled.
+    //
+    pub failed_by_kind: HashMap<TransErrKind, usize>,
+
+    /// The errors of this batch, in input order. Empty if the errors were sent to an [`ErrorSink`]
+    /// with [`Bank::process_into`], or if the bank retains errors, see [`Bank::set_retain_errors`].
+    //
+    pub errors: Vec<TransErr>,
+
+    /// The number of distinct clients affected by the applied transactions.
+    //
+    pub clients_touched: usize,
+
+    /// How long processing the batch took.
+    //
+    pub elapsed: Duration,
+}
+
+impl ProcessReport {
+    /// The number of transactions in the batch, successful or not.
+    //
+    pub fn processed(&self) -> usize {
+        self.succeeded + self.failed
+    }
+
+    /// The number of transactions rejected with the given kind of error.
+    //
+    pub fn failed_with(&self, kind: TransErrKind) -> usize {
+        self.failed_by_kind.get(&kind).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,84 @@
+use crate::import::*;
+
+/// Why a row or the header of the input could not be read, see [`TransErr::DeserializeCsv`] and
+/// [`TransErr::DeserializeHeader`](crate::TransErr::DeserializeHeader).
+///
+/// For [`CsvParse`](crate::CsvParse) this is an error from the _csv_ crate, which is available
+/// with [`RowError::csv`]. The other parsers describe the problem themselves, so they don't need
+/// the _csv_ crate.
+//
+#[derive(Debug)]
+//
+pub struct RowError {
+    inner: Inner,
+}
+
+// The csv crate's error, or a description of the problem from the other parsers.
+//
+#[derive(Debug)]
+//
+enum Inner {
+    #[cfg(feature = "csv")]
+    //
+    Csv(csv::Error),
+
+    Io(io::Error),
+}
+
+impl RowError {
+    /// The error from the _csv_ crate, if this is one.
+    //
+    #[cfg(feature = "csv")]
+    #[cfg_attr(nightly, doc(cfg(feature = "csv")))]
+    //
+    pub fn csv(&self) -> Option<&csv::Error> {
+        match &self.inner {
+            Inner::Csv(e) => Some(e),
+            Inner::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            #[cfg(feature = "csv")]
+            //
+            Inner::Csv(e) => fmt::Display::fmt(e, f),
+
+            Inner::Io(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+// The message is the one of the wrapped error, so continue the chain with its source.
+//
+impl std::error::Error for RowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.inner {
+            #[cfg(feature = "csv")]
+            //
+            Inner::Csv(e) => std::error::Error::source(e),
+
+            Inner::Io(e) => std::error::Error::source(e),
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+//
+impl From<csv::Error> for RowError {
+    fn from(e: csv::Error) -> Self {
+        Self {
+            inner: Inner::Csv(e),
+        }
+    }
+}
+
+impl From<io::Error> for RowError {
+    fn from(e: io::Error) -> Self {
+        Self {
+            inner: Inner::Io(e),
+        }
+    }
+}
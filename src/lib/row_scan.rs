@@ -0,0 +1,247 @@
+use crate::import::*;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Watches the raw input on its way to the csv reader, for what csv doesn't tell us: blank lines,
+/// which it always skips, rows that are too long, before it buffers them whole, whether there
+/// was a byte order mark, which it always strips, and the raw bytes of each row, as csv only
+/// gives us the unquoted fields.
+///
+/// To find where rows end it tracks quotes like csv does: a quote only opens a quoted field when it
+/// is the first byte of the field, at the start of a row or right after a delimiter. Other quotes
+/// in unquoted fields are just data.
+//
+#[derive(Debug)]
+//
+pub(crate) struct RowScan<R> {
+    inner: R,
+
+    delimiter: u8,
+    quote: Option<u8>,
+    comment: Option<u8>,
+    blank_lines: bool,
+    max_row_len: Option<usize>,
+
+    // Position of the next byte.
+    //
+    line: u64,
+    byte: u64,
+
+    line_start: bool,
+    field_start: bool,
+    in_quotes: bool,
+
+    // A quote ended the quoted field, unless the next byte is a quote, which escapes it.
+    //
+    closed_quote: bool,
+    in_comment: bool,
+    prev_cr: bool,
+
+    // Start and length so far of the current row.
+    //
+    row: (u64, u64),
+    row_len: usize,
+
+    /// Positions of blank lines not yet taken by the parser, as line and byte.
+    //
+    pub(crate) blanks: std::collections::VecDeque<(u64, u64)>,
+
+    /// The position of a row that exceeded the maximum length. Nothing is read after that.
+    //
+    pub(crate) too_long: Option<(u64, u64)>,
+
+    /// Whether the input started with a UTF-8 byte order mark.
+    //
+    pub(crate) bom: bool,
+
+    // The bytes passed on that are not yet taken by `take_row`, starting at byte `raw_start`.
+    //
+    raw: Vec<u8>,
+    raw_start: u64,
+}
+
+impl<R> RowScan<R> {
+    /// Scan `inner`. Blank lines are only recorded if `blank_lines` is set.
+    //
+    pub(crate) fn new(
+        inner: R,
+        delimiter: u8,
+        quote: Option<u8>,
+        comment: Option<u8>,
+        blank_lines: bool,
+        max_row_len: Option<usize>,
+    ) -> Self {
+        Self {
+            inner,
+            delimiter,
+            quote,
+            comment,
+            blank_lines,
+            max_row_len,
+            line: 1,
+            byte: 0,
+            line_start: true,
+            field_start: true,
+            in_quotes: false,
+            closed_quote: false,
+            in_comment: false,
+            prev_cr: false,
+            row: (1, 0),
+            row_len: 0,
+            blanks: Default::default(),
+            too_long: None,
+            bom: false,
+            raw: Vec::new(),
+            raw_start: 0,
+        }
+    }
+
+    /// The raw bytes of the row csv read between the bytes `start` and `end`, without the line
+    /// ending. Everything before `end` is dropped, so rows must be taken in order.
+    //
+    pub(crate) fn take_row(&mut self, start: u64, end: u64) -> Vec<u8> {
+        let offset = |pos: u64| (pos.saturating_sub(self.raw_start) as usize).min(self.raw.len());
+        let (from, to) = (offset(start), offset(end));
+
+        let mut row = &self.raw[from..to];
+
+        // Like csv, a record starts where the previous one ended, so skip what came in between.
+        //
+        loop {
+            row = match row {
+                [b'\n' | b'\r', rest @ ..] => rest,
+                [first, ..] if Some(*first) == self.comment => {
+                    match row.iter().position(|b| *b == b'\n') {
+                        Some(i) => &row[i + 1..],
+                        None => &[],
+                    }
+                }
+                _ if row.starts_with(BOM) => &row[BOM.len()..],
+                _ => break,
+            };
+        }
+
+        while let [rest @ .., b'\n' | b'\r'] = row {
+            row = rest;
+        }
+
+        let row = row.to_vec();
+
+        self.raw.drain(..to);
+        self.raw_start += to as u64;
+
+        row
+    }
+
+    // Scan the bytes. Returns how many may be passed on, which is less than all of them when a row
+    // is too long.
+    //
+    fn scan(&mut self, buf: &[u8]) -> usize {
+        for (i, &b) in buf.iter().enumerate() {
+            let terminator = b == b'\n' || b == b'\r';
+
+            if self.in_comment {
+                if terminator {
+                    self.in_comment = false;
+                    self.line_start = true;
+                }
+            } else if self.in_quotes {
+                self.in_quotes = Some(b) != self.quote;
+                self.closed_quote = !self.in_quotes;
+                self.row_len += 1;
+            } else if self.closed_quote && Some(b) == self.quote {
+                self.in_quotes = true;
+                self.closed_quote = false;
+                self.row_len += 1;
+            } else if terminator {
+                self.closed_quote = false;
+
+                // The \n of a \r\n was handled with the \r.
+                //
+                if self.line_start && !(b == b'\n' && self.prev_cr) && self.blank_lines {
+                    self.blanks.push_back((self.line, self.byte));
+                }
+
+                self.line_start = true;
+            } else {
+                if self.line_start {
+                    self.line_start = false;
+                    self.in_comment = Some(b) == self.comment;
+                    self.row = (self.line, self.byte);
+                    self.row_len = 0;
+                    self.field_start = true;
+                }
+
+                self.in_quotes = self.field_start && Some(b) == self.quote;
+                self.field_start = b == self.delimiter;
+                self.closed_quote = false;
+                self.row_len += 1;
+            }
+
+            if self.max_row_len.is_some_and(|max| self.row_len > max) {
+                self.too_long = Some(self.row);
+                return i;
+            }
+
+            self.prev_cr = b == b'\r';
+            self.byte += 1;
+
+            if b == b'\n' {
+                self.line += 1;
+            }
+        }
+
+        buf.len()
+    }
+
+    fn too_long_err(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "row is longer than the maximum of {} bytes",
+                self.max_row_len.unwrap_or_default()
+            ),
+        )
+    }
+}
+
+impl<R: io::Read> io::Read for RowScan<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.too_long.is_some() {
+            return Err(self.too_long_err());
+        }
+
+        let n = self.inner.read(buf)?;
+
+        // Like csv, only look for the byte order mark in the first read.
+        //
+        if self.byte == 0 && buf[..n].starts_with(BOM) {
+            self.bom = true;
+        }
+
+        if !self.blank_lines && self.max_row_len.is_none() {
+            self.byte += n as u64;
+            self.raw.extend_from_slice(&buf[..n]);
+            return Ok(n);
+        }
+
+        // A byte order mark is not part of the first row.
+        //
+        let skip = match self.byte == 0 && self.bom {
+            true => BOM.len(),
+            false => 0,
+        };
+
+        self.byte += skip as u64;
+
+        let n = skip + self.scan(&buf[skip..n]);
+        self.raw.extend_from_slice(&buf[..n]);
+
+        // Returning 0 would look like the end of the input.
+        //
+        match n == 0 && self.too_long.is_some() {
+            true => Err(self.too_long_err()),
+            false => Ok(n),
+        }
+    }
+}
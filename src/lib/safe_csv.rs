@@ -0,0 +1,451 @@
+//! A CSV parser for the transaction format that doesn't depend on the _csv_ crate for parsing,
+//! and thus on the unsafe code it pulls in. See the README for the rationale.
+//
+#![forbid(unsafe_code)]
+
+use crate::{
+    column_map::{field_count, ColumnMap},
+    import::*,
+    position::{position, with_position},
+    transaction::*,
+    CsvLayout, TransErr,
+};
+use std::{io::BufRead as _, ops::Range};
+
+/// A csv source for transactions that is a drop in replacement for [`CsvParse`](crate::CsvParse).
+/// It accepts the same format and yields the same transactions and errors, but is implemented
+/// without unsafe code. Requires the `safe_csv` feature.
+///
+/// Like with [`CsvParse`](crate::CsvParse):
+///
+/// - whitespace around fields and blank lines are ignored,
+/// - fields can be quoted with `"`, a quote in a quoted field is escaped by doubling it,
+/// - lines end with `\n`, `\r\n` or `\r`,
+/// - a UTF-8 byte order mark at the start of the input is ignored,
+/// - a row with invalid UTF-8 or the wrong number of fields is reported as an error and skipped,
+/// - the columns are found as described by [`CsvLayout`].
+///
+/// Errors are a [`RowError`](crate::RowError) without an error of the _csv_ crate, the message
+/// describes the problem.
+///
+/// For untrusted input, limit the length of rows with [`SafeCsvParse::with_max_row_len`], like
+/// [`CsvParseBuilder::max_row_len`](crate::CsvParseBuilder::max_row_len) does.
+//
+#[cfg_attr(nightly, doc(cfg(feature = "safe_csv")))]
+//
+pub struct SafeCsvParse<R> {
+    records: Records<R>,
+
+    // Set after an I/O error or a row that is too long, so we don't keep retrying.
+    //
+    done: bool,
+
+    columns: ColumnMap,
+
+    // Like csv, every record must have as many fields as the first one, header or not.
+    //
+    record_len: Option<usize>,
+}
+
+// Splits the input in records.
+//
+struct Records<R> {
+    reader: io::BufReader<R>,
+
+    // Position of the next byte to read.
+    //
+    line: u64,
+    byte: u64,
+
+    max_row_len: Option<usize>,
+
+    // Where the row that was too long starts, it ends the input.
+    //
+    too_long: Option<(u64, u64)>,
+}
+
+// A record as read from the input, before unquoting, utf8 validation and trimming.
+//
+struct RawRecord {
+    line: u64,
+    byte: u64,
+
+    // The bytes of the row as read, without the line ending.
+    //
+    row: Vec<u8>,
+
+    // Where each field is in the row, with its quotes.
+    //
+    fields: Vec<Range<usize>>,
+}
+
+// States of the parser within a record.
+//
+#[derive(Clone, Copy)]
+//
+enum State {
+    // Nothing read yet, line endings here are blank lines.
+    //
+    StartRecord,
+    StartField,
+    InField,
+    InQuoted,
+
+    // A quote in a quoted field, either the end of the field or the first of an escaped quote.
+    //
+    QuoteInQuoted,
+}
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+impl<R: io::Read> SafeCsvParse<R> {
+    /// Create a new source for Csv data. Reads and verifies the header.
+    //
+    pub fn new(reader: R) -> Result<Self, TransErr> {
+        Self::with_layout(reader, &CsvLayout::default())
+    }
+
+    /// Create a new source for Csv data with the given layout of columns.
+    //
+    pub fn with_layout(reader: R, layout: &CsvLayout) -> Result<Self, TransErr> {
+        Self::with_max_row_len(reader, layout, None)
+    }
+
+    /// Create a new source for Csv data with the given layout of columns and maximum length of a
+    /// row in bytes, not counting the line ending. A longer row is reported as a
+    /// [`TransErr::DeserializeCsv`] and ends the input, so a malicious file can't make us buffer
+    /// an arbitrary amount of data. `None` means no limit.
+    //
+    pub fn with_max_row_len(
+        reader: R,
+        layout: &CsvLayout,
+        max_row_len: Option<usize>,
+    ) -> Result<Self, TransErr> {
+        let header_err = |source| TransErr::DeserializeHeader { source };
+
+        let mut records = Records {
+            reader: io::BufReader::new(reader),
+            line: 1,
+            byte: 0,
+            max_row_len,
+            too_long: None,
+        };
+
+        records.skip_bom().map_err(|e| header_err(e.into()))?;
+
+        let (columns, record_len) = match layout.columns() {
+            Some(columns) => (ColumnMap::new(columns, layout.extra_columns())?, None),
+
+            None => {
+                let header = match records.read() {
+                    Ok(Some(header)) => header,
+                    Ok(None) => return Err(TransErr::NoHeader),
+                    Err(e) => return Err(header_err(e.into())),
+                };
+
+                let header = header.strings().map_err(|e| header_err(e.into()))?;
+                let columns = ColumnMap::from_header(header.iter(), layout.extra_columns())?;
+
+                (columns, Some(header.len()))
+            }
+        };
+
+        Ok(Self {
+            records,
+            done: false,
+            columns,
+            record_len,
+        })
+    }
+}
+
+impl<R: io::Read> Records<R> {
+    fn skip_bom(&mut self) -> io::Result<()> {
+        let buf = self.reader.fill_buf()?;
+
+        if buf.starts_with(BOM) {
+            self.reader.consume(BOM.len());
+            self.byte += BOM.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    // Read the next record, skipping blank lines. Returns None at the end of the input.
+    //
+    fn read(&mut self) -> io::Result<Option<RawRecord>> {
+        let mut fields = Vec::new();
+        let mut field = 0;
+        let mut row = Vec::new();
+        let mut state = State::StartRecord;
+
+        // Like csv, the position of a record is where the previous one ended, so it includes
+        // any blank lines before it.
+        //
+        let (line, byte) = (self.line, self.byte);
+
+        loop {
+            let buf = self.reader.fill_buf()?;
+
+            // End of input.
+            //
+            if buf.is_empty() {
+                if let State::StartRecord = state {
+                    return Ok(None);
+                }
+
+                // Like csv, an unterminated quote runs to the end, but the line ending is not
+                // part of the row.
+                //
+                while let Some(b'\n' | b'\r') = row.last() {
+                    row.pop();
+                }
+
+                fields.push(field.min(row.len())..row.len());
+
+                return Ok(Some(RawRecord {
+                    line,
+                    byte,
+                    row,
+                    fields,
+                }));
+            }
+
+            let mut used = 0;
+            let mut end = false;
+
+            for &b in buf {
+                used += 1;
+
+                if b == b'\n' {
+                    self.line += 1;
+                }
+
+                let terminator = b == b'\n' || b == b'\r';
+
+                state = match (state, b) {
+                    (State::StartRecord, _) if terminator => State::StartRecord,
+
+                    (State::StartRecord | State::StartField, b'"') => State::InQuoted,
+
+                    (State::StartRecord | State::StartField | State::InField, b',')
+                    | (State::QuoteInQuoted, b',') => {
+                        fields.push(field..row.len());
+                        field = row.len() + 1;
+                        State::StartField
+                    }
+
+                    (State::StartField | State::InField | State::QuoteInQuoted, _)
+                        if terminator =>
+                    {
+                        end = true;
+                        break;
+                    }
+
+                    (State::InQuoted, b'"') => State::QuoteInQuoted,
+                    (State::InQuoted, _) | (State::QuoteInQuoted, b'"') => State::InQuoted,
+                    (_, _) => State::InField,
+                };
+
+                // Line endings are only part of the row within quotes.
+                //
+                if !terminator || matches!(state, State::InQuoted) {
+                    row.push(b);
+                }
+
+                if self.max_row_len.is_some_and(|max| row.len() > max) {
+                    self.too_long = Some((line, byte));
+                    return Err(self.too_long_err());
+                }
+            }
+
+            self.reader.consume(used);
+            self.byte += used as u64;
+
+            if end {
+                fields.push(field..row.len());
+
+                return Ok(Some(RawRecord {
+                    line,
+                    byte,
+                    row,
+                    fields,
+                }));
+            }
+        }
+    }
+
+    fn too_long_err(&self) -> io::Error {
+        invalid(format!(
+            "row is longer than the maximum of {} bytes",
+            self.max_row_len.unwrap_or_default()
+        ))
+    }
+}
+
+impl RawRecord {
+    // Unquote the fields, validate utf8 and trim them.
+    //
+    fn strings(&self) -> io::Result<Vec<Cow<'_, str>>> {
+        self.fields
+            .iter()
+            .map(|range| match unquote(&self.row[range.clone()]) {
+                Cow::Borrowed(f) => std::str::from_utf8(f).map(|f| Cow::Borrowed(f.trim())),
+
+                Cow::Owned(f) => String::from_utf8(f)
+                    .map(|f| Cow::Owned(f.trim().to_string()))
+                    .map_err(|e| e.utf8_error()),
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|e| invalid(format!("invalid utf-8: {e}")))
+    }
+}
+
+// A field as it is in the row. Only a quote at the start opens a quoted field. Within it a quote
+// is escaped by doubling it, anything after the closing quote is part of the field.
+//
+fn unquote(field: &[u8]) -> Cow<'_, [u8]> {
+    let Some(quoted) = field.strip_prefix(b"\"") else {
+        return Cow::Borrowed(field);
+    };
+
+    let mut out = Vec::with_capacity(quoted.len());
+    let mut bytes = quoted.iter().copied().peekable();
+    let mut in_quotes = true;
+
+    while let Some(b) = bytes.next() {
+        match b == b'"' && in_quotes {
+            true => match bytes.next_if_eq(&b'"') {
+                Some(q) => out.push(q),
+                None => in_quotes = false,
+            },
+
+            false => out.push(b),
+        }
+    }
+
+    Cow::Owned(out)
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Deserialize the fields like csv does for `CsvRecord`.
+//
+fn deserialize(fields: [&str; 4]) -> io::Result<CsvRecord<'_>> {
+    let parse_err = |name: &str, e: std::num::ParseIntError| invalid(format!("field {name}: {e}"));
+
+    Ok(CsvRecord {
+        r#type: Cow::Borrowed(fields[0]),
+        client: fields[1].parse().map_err(|e| parse_err("client", e))?,
+        tx: fields[2].parse().map_err(|e| parse_err("tx", e))?,
+        amount: match fields[3] {
+            "" => None,
+            a => Some(Cow::Borrowed(a)),
+        },
+        reason: None,
+    })
+}
+
+impl<R: io::Read> Iterator for SafeCsvParse<R> {
+    type Item = Result<Transact, TransErr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let raw = match self.records.read() {
+            Ok(Some(raw)) => raw,
+            Ok(None) => return None,
+
+            // A row that is too long ends the input, like any I/O error.
+            //
+            Err(e) => {
+                self.done = true;
+
+                let pos = self
+                    .records
+                    .too_long
+                    .map(|(line, byte)| position(line, byte, Vec::new()));
+
+                return Some(Err(TransErr::DeserializeCsv {
+                    source: e.into(),
+                    pos,
+                }));
+            }
+        };
+
+        let record_err = |e: io::Error| TransErr::DeserializeCsv {
+            source: e.into(),
+            pos: Some(position(raw.line, raw.byte, raw.row.clone())),
+        };
+
+        let record_len = *self.record_len.get_or_insert(raw.fields.len());
+
+        if raw.fields.len() != record_len {
+            return Some(Err(record_err(field_count(raw.fields.len(), record_len))));
+        }
+
+        let fields = match raw.strings() {
+            Ok(fields) => fields,
+            Err(e) => return Some(Err(record_err(e))),
+        };
+
+        let pos = position(raw.line, raw.byte, raw.row.clone());
+        let get = |i: usize| fields.get(i).map(|f| &**f);
+
+        // Without a header the number of fields is not checked against it yet.
+        //
+        if fields.len() != self.columns.len() {
+            let source = field_count(fields.len(), self.columns.len()).into();
+            return Some(with_position(
+                Err(TransErr::DeserializeCsv { source, pos: None }),
+                pos,
+            ));
+        }
+
+        let result = match deserialize(self.columns.fields(get)) {
+            Ok(r) => Transact::try_from(r),
+            Err(e) => Err(TransErr::DeserializeCsv {
+                source: e.into(),
+                pos: None,
+            }),
+        };
+
+        let result = result.map(|mut trans| {
+            trans.extra = self.columns.extra(get);
+            trans
+        });
+
+        Some(with_position(result, pos))
+    }
+}
+
+impl<R> fmt::Debug for SafeCsvParse<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SafeCsvParse")
+    }
+}
+
+impl TryFrom<&'static str> for SafeCsvParse<&[u8]> {
+    type Error = TransErr;
+
+    fn try_from(s: &'static str) -> Result<SafeCsvParse<&'static [u8]>, TransErr> {
+        SafeCsvParse::new(s.trim().as_bytes())
+    }
+}
+
+impl TryFrom<&Path> for SafeCsvParse<File> {
+    type Error = TransErr;
+
+    fn try_from(p: &Path) -> Result<SafeCsvParse<File>, TransErr> {
+        let file = File::open(p).map_err(|e| TransErr::InputFile {
+            source: e,
+            path: p.to_path_buf(),
+        })?;
+
+        SafeCsvParse::new(file)
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{codec::*, import::*, *};
+use std::io::{Read as _, Write as _};
+
+// Identifies a bank snapshot.
+//
+const MAGIC: &[u8; 8] = b"TRANSACT";
+
+/// The current version of the snapshot format. Bump when changing the layout and keep
+/// reading older versions where possible.
+///
+/// - 1: clients with the reason of their last unlock, transactions with the amount held by their
+///   open dispute unless the store is durable, and errors with their input position.
+//
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// Entry tags. A snapshot is a sequence of entries terminated by `END`.
+//
+const END: u8 = 0;
+const CLIENT: u8 = 1;
+const TRANSACT: u8 = 2;
+const ERROR: u8 = 3;
+const DURABLE: u8 = 4;
+
+impl<T: TransactionStore, A: AccountStore> Bank<T, A> {
+    /// Write the full state of the bank to `out`: all clients, all stored transactions with their
+    /// state and the amount held by their open dispute, and the retained errors. Settings like the
+    /// [`DisputePolicy`] are not part of the snapshot.
+    ///
+    /// If the transaction store is durable (see [`TransactionStore::is_durable`]) the transactions
+    /// are left out, as the store already keeps them. Such a snapshot can only be restored together
+    /// with that store.
+    ///
+    /// The format is a versioned binary format:
+    ///
+    /// ```text
+    /// magic: "TRANSACT" | version: u32 | entries... | end: u8
+    /// ```
+    ///
+    /// Where each entry is a tag byte followed by a length prefixed payload. The output is buffered
+    /// internally.
+    //
+    pub fn snapshot(&self, out: impl io::Write) -> Result<(), TransErr> {
+        let err = |source| TransErr::Snapshot { source };
+        let mut out = io::BufWriter::new(out);
+
+        out.write_all(MAGIC).map_err(err)?;
+        out.write_all(&SNAPSHOT_VERSION.to_le_bytes())
+            .map_err(err)?;
+
+        let mut enc = Encoder::default();
+
+        for client in self.clients.iter() {
+            enc.u16(client.id)
+                .u8(client.locked as u8)
+                .amount(client.available)
+                .amount(client.held);
+
+            match &client.unlock_reason {
+                Some(reason) => enc.u8(1).str(reason),
+                None => enc.u8(0),
+            };
+
+            write_entry(&mut out, CLIENT, &mut enc).map_err(err)?;
+        }
+
+        if self.db.is_durable() {
+            write_entry(&mut out, DURABLE, &mut enc).map_err(err)?;
+        } else {
+            for trans in self.db.iter() {
+                let trans = trans?;
+                enc.transact(&trans);
+
+                match self.db.held(trans.id)? {
+                    Some(held) => enc.u8(1).amount(held),
+                    None => enc.u8(0),
+                };
+
+                write_entry(&mut out, TRANSACT, &mut enc).map_err(err)?;
+            }
+        }
+
+        for e in &self.errors {
+            encode_error(&mut enc, e);
+            write_entry(&mut out, ERROR, &mut enc).map_err(err)?;
+        }
+
+        out.write_all(&[END]).map_err(err)?;
+        out.flush().map_err(err)
+    }
+
+    /// Restore a bank from a snapshot created with [`Bank::snapshot`] into the given stores.
+    /// The account store is expected to be empty. Transactions the transaction store already
+    /// holds are not inserted again.
+    ///
+    /// A snapshot of a bank with a durable transaction store can only be restored into a durable
+    /// store, as it doesn't contain the transactions.
+    ///
+    /// Errors that wrapped an I/O or CSV error are restored with their message only.
+    //
+    pub fn restore_with_stores(input: impl io::Read, db: T, clients: A) -> Result<Self, TransErr> {
+        let mut bank = Self::with_stores(db, clients);
+
+        bank.read_snapshot(io::BufReader::new(input))?;
+
+        Ok(bank)
+    }
+
+    fn read_snapshot(&mut self, mut input: impl io::Read) -> Result<(), TransErr> {
+        let err = |source| TransErr::Snapshot { source };
+
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic).map_err(err)?;
+
+        if &magic != MAGIC {
+            return Err(err(corrupt("not a bank snapshot")));
+        }
+
+        let mut version = [0u8; 4];
+        input.read_exact(&mut version).map_err(err)?;
+
+        let version = u32::from_le_bytes(version);
+
+        if version != SNAPSHOT_VERSION {
+            return Err(err(corrupt("unsupported snapshot version")));
+        }
+
+        let mut payload = Vec::new();
+
+        loop {
+            let mut header = [0u8; 5];
+
+            input.read_exact(&mut header[..1]).map_err(err)?;
+
+            if header[0] == END {
+                return Ok(());
+            }
+
+            input.read_exact(&mut header[1..]).map_err(err)?;
+
+            let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+
+            payload.clear();
+            (&mut input)
+                .take(len as u64)
+                .read_to_end(&mut payload)
+                .map_err(err)?;
+
+            if payload.len() != len as usize {
+                return Err(err(io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            let mut dec = Decoder::new(&payload);
+
+            match header[0] {
+                CLIENT => {
+                    let id = dec.u16().map_err(err)?;
+                    let locked = dec.u8().map_err(err)? != 0;
+                    let available = dec.amount().map_err(err)?;
+                    let held = dec.amount().map_err(err)?;
+
+                    let unlock_reason = match dec.u8().map_err(err)? {
+                        0 => None,
+                        _ => Some(dec.str().map_err(err)?.to_string()),
+                    };
+
+                    *self.clients.get_or_create(id) = Client {
+                        available,
+                        held,
+                        id,
+                        locked,
+                        unlock_reason,
+                    };
+                }
+
+                TRANSACT => {
+                    let trans = dec.transact().map_err(err)?;
+
+                    let held = match dec.u8().map_err(err)? {
+                        0 => None,
+                        _ => Some(dec.amount().map_err(err)?),
+                    };
+
+                    if !self.db.contains(trans.id)? {
+                        let id = trans.id;
+                        self.db.insert(trans)?;
+
+                        if let Some(held) = held {
+                            self.db.set_disputed(id, held)?;
+                        }
+                    }
+                }
+
+                DURABLE if !self.db.is_durable() => {
+                    return Err(err(corrupt(
+                        "the snapshot expects the transactions in a durable store",
+                    )));
+                }
+
+                DURABLE => {}
+
+                ERROR => {
+                    let e = decode_error(&mut dec).map_err(err)?;
+                    self.errors.push(e);
+                }
+
+                _ => return Err(err(corrupt("unknown snapshot entry"))),
+            }
+
+            if !dec.is_empty() {
+                return Err(err(corrupt("trailing data in snapshot entry")));
+            }
+        }
+    }
+}
+
+impl Bank {
+    /// Restore an in-memory bank from a snapshot created with [`Bank::snapshot`].
+    //
+    pub fn restore(input: impl io::Read) -> Result<Self, TransErr> {
+        Self::restore_with_stores(input, HashMap::new(), HashMap::new())
+    }
+}
+
+// Write the contents of the encoder as an entry and clear it.
+//
+fn write_entry(out: &mut impl io::Write, tag: u8, enc: &mut Encoder) -> io::Result<()> {
+    out.write_all(&[tag])?;
+    out.write_all(&(enc.buf.len() as u32).to_le_bytes())?;
+    out.write_all(&enc.buf)?;
+
+    enc.buf.clear();
+    Ok(())
+}
+
+fn encode_error(enc: &mut Encoder, err: &TransErr) {
+    match err {
+        TransErr::InputFile { source, path } => enc
+            .u8(0)
+            .str(&source.to_string())
+            .str(&path.to_string_lossy()),
+        TransErr::Store { source, path } => enc
+            .u8(1)
+            .str(&source.to_string())
+            .str(&path.to_string_lossy()),
+        TransErr::DeserializeCsv { source, .. } => enc.u8(2).str(&source.to_string()),
+        TransErr::DeserializeTransact { kind, record, .. } => {
+            enc.u8(3)
+                .u8(match kind {
+                    DeserTransactKind::AmountNegative => 0,
+                    DeserTransactKind::UnknownTransType => 1,
+                    DeserTransactKind::AmountZero => 2,
+                    DeserTransactKind::MissingAmount => 3,
+                    DeserTransactKind::UnexpectedAmount => 4,
+                    DeserTransactKind::TooManyDecimals => 5,
+                    DeserTransactKind::InvalidAmount => 6,
+                    DeserTransactKind::MissingReason => 7,
+                })
+                .str(&record.r#type)
+                .u16(record.client)
+                .u32(record.tx);
+
+            match &record.amount {
+                Some(a) => enc.u8(1).str(a),
+                None => enc.u8(0),
+            }
+        }
+        TransErr::DeserializeHeader { source } => enc.u8(4).str(&source.to_string()),
+        TransErr::NoHeader => enc.u8(5),
+        TransErr::SerializeClients { .. } => enc.u8(6),
+        TransErr::Snapshot { source } => enc.u8(7).str(&source.to_string()),
+        TransErr::DuplicateTransact { trans } => enc.u8(8).transact(trans),
+        TransErr::AccountLocked { trans } => enc.u8(9).transact(trans),
+        TransErr::InsufficientFunds { trans } => enc.u8(10).transact(trans),
+        TransErr::NoClient { trans } => enc.u8(11).transact(trans),
+        TransErr::WrongClient { trans } => enc.u8(12).transact(trans),
+        TransErr::WrongTransState { trans } => enc.u8(13).transact(trans),
+        TransErr::ReferNoneExisting { trans } => enc.u8(14).transact(trans),
+        TransErr::ShouldBeDeposit { trans } => enc.u8(15).transact(trans),
+        TransErr::Overflow { trans } => enc.u8(16).transact(trans),
+        TransErr::Unauthorized { trans } => enc.u8(17).transact(trans),
+        TransErr::AccountNotLocked { trans } => enc.u8(18).transact(trans),
+        TransErr::Rejects { source, path } => enc
+            .u8(19)
+            .str(&source.to_string())
+            .str(&path.to_string_lossy()),
+    };
+
+    enc.position(err.position());
+}
+
+fn decode_error(dec: &mut Decoder<'_>) -> io::Result<TransErr> {
+    let io_err = |msg: &str| io::Error::other(msg.to_string());
+    let csv_err = |msg: &str| RowError::from(io_err(msg));
+
+    let mut e = match dec.u8()? {
+        0 => TransErr::InputFile {
+            source: io_err(dec.str()?),
+            path: PathBuf::from(dec.str()?),
+        },
+        1 => TransErr::Store {
+            source: io_err(dec.str()?),
+            path: PathBuf::from(dec.str()?),
+        },
+        2 => TransErr::DeserializeCsv {
+            source: csv_err(dec.str()?),
+            pos: None,
+        },
+        3 => {
+            let kind = match dec.u8()? {
+                0 => DeserTransactKind::AmountNegative,
+                1 => DeserTransactKind::UnknownTransType,
+                2 => DeserTransactKind::AmountZero,
+                3 => DeserTransactKind::MissingAmount,
+                4 => DeserTransactKind::UnexpectedAmount,
+                5 => DeserTransactKind::TooManyDecimals,
+                6 => DeserTransactKind::InvalidAmount,
+                7 => DeserTransactKind::MissingReason,
+                _ => return Err(corrupt("unknown error kind")),
+            };
+
+            let record = CsvRecord {
+                r#type: Cow::Owned(dec.str()?.to_string()),
+                client: dec.u16()?,
+                tx: dec.u32()?,
+                amount: match dec.u8()? {
+                    0 => None,
+                    _ => Some(Cow::Owned(dec.str()?.to_string())),
+                },
+                reason: None,
+            };
+
+            TransErr::DeserializeTransact {
+                kind,
+                record,
+                pos: None,
+            }
+        }
+        4 => TransErr::DeserializeHeader {
+            source: csv_err(dec.str()?),
+        },
+        5 => TransErr::NoHeader,
+        6 => TransErr::SerializeClients {
+            source: io::Error::other(fmt::Error),
+        },
+        7 => TransErr::Snapshot {
+            source: io_err(dec.str()?),
+        },
+        8 => TransErr::DuplicateTransact {
+            trans: dec.transact()?,
+        },
+        9 => TransErr::AccountLocked {
+            trans: dec.transact()?,
+        },
+        10 => TransErr::InsufficientFunds {
+            trans: dec.transact()?,
+        },
+        11 => TransErr::NoClient {
+            trans: dec.transact()?,
+        },
+        12 => TransErr::WrongClient {
+            trans: dec.transact()?,
+        },
+        13 => TransErr::WrongTransState {
+            trans: dec.transact()?,
+        },
+        14 => TransErr::ReferNoneExisting {
+            trans: dec.transact()?,
+        },
+        15 => TransErr::ShouldBeDeposit {
+            trans: dec.transact()?,
+        },
+        16 => TransErr::Overflow {
+            trans: dec.transact()?,
+        },
+        17 => TransErr::Unauthorized {
+            trans: dec.transact()?,
+        },
+        18 => TransErr::AccountNotLocked {
+            trans: dec.transact()?,
+        },
+        19 => TransErr::Rejects {
+            source: io_err(dec.str()?),
+            path: PathBuf::from(dec.str()?),
+        },
+        _ => return Err(corrupt("unknown error type")),
+    };
+
+    e.set_position(dec.position()?);
+
+    Ok(e)
+}
@@ -0,0 +1,175 @@
+use crate::{import::*, *};
+
+/// Storage for past transactions. The bank needs access to past transactions for dispute,
+/// resolve and chargeback, so every successful deposit and withdrawal is stored here.
+///
+/// All methods are fallible so implementations backed by a file or database can report
+/// failures. The in-memory implementation for `HashMap<u32, Transact>` never fails.
+//
+pub trait TransactionStore {
+    /// Whether a transaction with the given id has been stored.
+    //
+    fn contains(&self, id: u32) -> Result<bool, TransErr>;
+
+    /// Get the transaction with the given id.
+    //
+    fn get(&self, id: u32) -> Result<Option<Cow<'_, Transact>>, TransErr>;
+
+    /// Store a new transaction. The caller guarantees that the id does not exist yet.
+    //
+    fn insert(&mut self, trans: Transact) -> Result<(), TransErr>;
+
+    /// Update the state of a stored transaction and forget the amount held by its dispute, if any.
+    /// Does nothing if the id is unknown.
+    //
+    fn set_state(&mut self, id: u32, state: TransState) -> Result<(), TransErr>;
+
+    /// Mark a stored transaction as disputed and record the amount held by the dispute, which can be
+    /// less than the transaction amount with [`DisputePolicy::Partial`]. The store is the only place
+    /// the bank keeps this amount, so it must be kept until the next state change. Does nothing if
+    /// the id is unknown.
+    //
+    fn set_disputed(&mut self, id: u32, held: Amount) -> Result<(), TransErr>;
+
+    /// The amount held by the open dispute of a transaction, as recorded by
+    /// [`TransactionStore::set_disputed`]. Defaults to [`Transact::held`] of the stored
+    /// transaction, if it is disputed.
+    //
+    fn held(&self, id: u32) -> Result<Option<Amount>, TransErr> {
+        let trans = self.get(id)?;
+
+        Ok(trans
+            .filter(|t| t.state == TransState::Disputed)
+            .map(|t| t.held))
+    }
+
+    /// Iterate over all stored transactions. The order is up to the implementation.
+    //
+    fn iter(&self) -> Box<dyn Iterator<Item = Result<Cow<'_, Transact>, TransErr>> + '_>;
+
+    /// Whether the store keeps the transactions across runs by itself, like [`FileStore`].
+    /// [`Bank::snapshot`] leaves the transactions of a durable store out. Defaults to `false`.
+    //
+    fn is_durable(&self) -> bool {
+        false
+    }
+}
+
+impl TransactionStore for HashMap<u32, Transact> {
+    fn contains(&self, id: u32) -> Result<bool, TransErr> {
+        Ok(self.contains_key(&id))
+    }
+
+    fn get(&self, id: u32) -> Result<Option<Cow<'_, Transact>>, TransErr> {
+        Ok(HashMap::get(self, &id).map(Cow::Borrowed))
+    }
+
+    fn insert(&mut self, trans: Transact) -> Result<(), TransErr> {
+        HashMap::insert(self, trans.id, trans);
+        Ok(())
+    }
+
+    fn set_state(&mut self, id: u32, state: TransState) -> Result<(), TransErr> {
+        if let Some(t) = self.get_mut(&id) {
+            t.state = state;
+            t.held = Amount::ZERO;
+        }
+
+        Ok(())
+    }
+
+    fn set_disputed(&mut self, id: u32, held: Amount) -> Result<(), TransErr> {
+        if let Some(t) = self.get_mut(&id) {
+            t.state = TransState::Disputed;
+            t.held = held;
+        }
+
+        Ok(())
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = Result<Cow<'_, Transact>, TransErr>> + '_> {
+        Box::new(self.values().map(|t| Ok(Cow::Borrowed(t))))
+    }
+}
+
+/// Storage for client accounts.
+//
+pub trait AccountStore {
+    /// Get the client with the given id.
+    //
+    fn get(&self, id: u16) -> Option<&Client>;
+
+    /// Get the client with the given id (mutable).
+    //
+    fn get_mut(&mut self, id: u16) -> Option<&mut Client>;
+
+    /// Get the client with the given id, creating a new account if it doesn't exist.
+    //
+    fn get_or_create(&mut self, id: u16) -> &mut Client;
+
+    /// Iterate over all clients. The order is up to the implementation.
+    //
+    fn iter(&self) -> Box<dyn Iterator<Item = &Client> + '_>;
+}
+
+impl AccountStore for HashMap<u16, Client> {
+    fn get(&self, id: u16) -> Option<&Client> {
+        HashMap::get(self, &id)
+    }
+
+    fn get_mut(&mut self, id: u16) -> Option<&mut Client> {
+        HashMap::get_mut(self, &id)
+    }
+
+    fn get_or_create(&mut self, id: u16) -> &mut Client {
+        self.entry(id).or_insert_with(|| Client::new(id))
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Client> + '_> {
+        Box::new(self.values())
+    }
+}
+
+#[cfg(test)]
+//
+mod test {
+    use {
+        crate::{import::*, *},
+        pretty_assertions::assert_eq,
+    };
+
+    #[test]
+    fn hashmap_transactions() -> Result<(), TransErr> {
+        let mut db: HashMap<u32, Transact> = HashMap::new();
+
+        assert!(!db.contains(1)?);
+
+        TransactionStore::insert(&mut db, Transact::new(TransType::Dispute, 1, 1))?;
+        db.set_state(1, TransState::Disputed)?;
+
+        assert!(db.contains(1)?);
+        assert_eq!(
+            TransactionStore::get(&db, 1)?.map(|t| t.state),
+            Some(TransState::Disputed)
+        );
+
+        // unknown ids are ignored.
+        //
+        db.set_state(2, TransState::Disputed)?;
+        assert!(!db.contains(2)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hashmap_accounts() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+
+        assert!(AccountStore::get(&clients, 1).is_none());
+
+        clients.get_or_create(1).lock();
+
+        assert!(AccountStore::get(&clients, 1).unwrap().is_locked());
+        assert_eq!(AccountStore::iter(&clients).count(), 1);
+    }
+}
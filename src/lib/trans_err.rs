@@ -4,7 +4,7 @@ use crate::{import::*, *};
 
 /// Different things that can go wrong in deserialization after csv has correctly
 /// deserialized. This means the types were correct, but values are not compatible
-/// with a
+/// with a valid transaction.
 //
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 //
@@ -13,10 +13,35 @@ pub enum DeserTransactKind {
     //
     AmountNegative,
 
+    /// The csv file holds an amount of zero for deposit or withdrawal.
+    //
+    AmountZero,
+
     /// The transaction type is unknown. Only "deposit", "withdrawal", "dispute", "resolve" and chargeback
-    /// are supported.
+    /// are supported, and "unlock" from admin input.
     //
     UnknownTransType,
+
+    /// A deposit or withdrawal without an amount.
+    //
+    MissingAmount,
+
+    /// A dispute, resolve, chargeback or unlock with an amount. The first three refer to the amount
+    /// of an earlier transaction, so they should not have one.
+    //
+    UnexpectedAmount,
+
+    /// The amount has more than four decimal places. Amounts are rejected rather than rounded.
+    //
+    TooManyDecimals,
+
+    /// The amount is not a decimal number or too large to be represented.
+    //
+    InvalidAmount,
+
+    /// An unlock from admin input without a reason.
+    //
+    MissingReason,
 }
 
 /// The error type for errors happening in libtransact.
@@ -27,33 +52,47 @@ pub enum DeserTransactKind {
 pub enum TransErr {
     /// Cannot open input file.
     //
-    InputFile {
-        source: std::io::Error,
-        path: PathBuf,
-    },
+    InputFile { source: io::Error, path: PathBuf },
 
-    /// The input contained a transaction line that is invalid. These are errors from the csv crate.
+    /// The input contained a transaction line that is invalid, eg. a row the csv parser rejects or
+    /// a line that is not valid JSON for [`JsonLinesParse`].
     //
-    DeserializeCsv { source: csv::Error },
+    DeserializeCsv {
+        source: RowError,
+        pos: Option<Box<Position>>,
+    },
 
     /// The input contained a transaction line that is invalid.
     //
     DeserializeTransact {
         kind: DeserTransactKind,
         record: CsvRecord<'static>,
+        pos: Option<Box<Position>>,
     },
 
     /// The header could not be deserialized. Most likely it's not valid utf8.
     //
-    DeserializeHeader { source: csv::Error },
+    DeserializeHeader { source: RowError },
 
     /// The Csv file did not contain a valid header.
     //
     NoHeader,
 
-    /// Failed to export CSV.
+    /// Reading from or writing to the transaction store failed.
+    //
+    Store { source: io::Error, path: PathBuf },
+
+    /// Writing or reading a bank snapshot failed, or the snapshot is invalid.
+    //
+    Snapshot { source: io::Error },
+
+    /// Failed to export the clients or the ledger, eg. because writing the output failed.
+    //
+    SerializeClients { source: io::Error },
+
+    /// Creating or writing the file with rejected rows failed, see [`RejectSink`].
     //
-    SerializeClients { source: fmt::Error },
+    Rejects { source: io::Error, path: PathBuf },
 
     /// A deposit or withdrawal with a transaction id that already exists came in.
     /// The transaction will be ignored as invalid.
@@ -90,19 +129,296 @@ pub enum TransErr {
     //
     ReferNoneExisting { trans: Transact },
 
-    /// Disputed transaction must be a deposit.
+    /// The resulting balance can not be represented in an [`Amount`].
+    /// The transaction will be ignored as invalid.
+    //
+    Overflow { trans: Transact },
+
+    /// Administrative transactions are only accepted from a privileged source.
+    /// The transaction will be ignored as invalid.
+    //
+    Unauthorized { trans: Transact },
+
+    /// Cannot unlock an account that isn't locked.
+    /// The transaction will be ignored as invalid.
+    //
+    AccountNotLocked { trans: Transact },
+
+    /// Disputed transaction must be a deposit. Withdrawals can be disputed if enabled with
+    /// [`Bank::set_withdrawal_disputes`].
     /// The transaction will be ignored as invalid.
     //
     ShouldBeDeposit { trans: Transact },
 }
 
+/// The variant of a [`TransErr`], without the data. See [`TransErr`] for the meaning of each.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+//
+pub enum TransErrKind {
+    InputFile,
+    DeserializeCsv,
+    DeserializeTransact,
+    DeserializeHeader,
+    NoHeader,
+    Store,
+    Snapshot,
+    SerializeClients,
+    Rejects,
+    DuplicateTransact,
+    AccountLocked,
+    InsufficientFunds,
+    NoClient,
+    WrongClient,
+    WrongTransState,
+    ReferNoneExisting,
+    Overflow,
+    Unauthorized,
+    AccountNotLocked,
+    ShouldBeDeposit,
+}
+
+/// The broad category of an error, see [`TransErr::category`].
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+//
+pub enum ErrorCategory {
+    /// The input is invalid, eg. a row that can't be parsed or a missing header.
+    //
+    Input,
+
+    /// The transaction is well formed but violates a rule of the bank, eg. insufficient funds.
+    //
+    BusinessRule,
+
+    /// Reading or writing a file, the transaction store or a snapshot failed.
+    //
+    Io,
+}
+
+impl ErrorCategory {
+    /// The name of the category: `input`, `business-rule` or `io`. Stable, like [`TransErrKind::code`].
+    //
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCategory::Input => "input",
+            ErrorCategory::BusinessRule => "business-rule",
+            ErrorCategory::Io => "io",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl TransErrKind {
+    /// All kinds of errors.
+    //
+    pub const ALL: [TransErrKind; 20] = [
+        TransErrKind::InputFile,
+        TransErrKind::DeserializeCsv,
+        TransErrKind::DeserializeTransact,
+        TransErrKind::DeserializeHeader,
+        TransErrKind::NoHeader,
+        TransErrKind::Store,
+        TransErrKind::Snapshot,
+        TransErrKind::SerializeClients,
+        TransErrKind::Rejects,
+        TransErrKind::DuplicateTransact,
+        TransErrKind::AccountLocked,
+        TransErrKind::InsufficientFunds,
+        TransErrKind::NoClient,
+        TransErrKind::WrongClient,
+        TransErrKind::WrongTransState,
+        TransErrKind::ReferNoneExisting,
+        TransErrKind::Overflow,
+        TransErrKind::Unauthorized,
+        TransErrKind::AccountNotLocked,
+        TransErrKind::ShouldBeDeposit,
+    ];
+
+    /// A stable, machine readable code for this kind of error, eg. `E_INSUFFICIENT_FUNDS`.
+    ///
+    /// These codes are part of the public API: they will not change and a code will not be
+    /// reused for a different error, so they can be relied on by alerting or by partners receiving
+    /// rejected rows. New kinds of errors get new codes. The error messages on the other hand
+    /// are meant for humans and may change at any time.
+    //
+    pub fn code(self) -> &'static str {
+        match self {
+            TransErrKind::InputFile => "E_INPUT_FILE",
+            TransErrKind::DeserializeCsv => "E_INVALID_ROW",
+            TransErrKind::DeserializeTransact => "E_INVALID_TX",
+            TransErrKind::DeserializeHeader => "E_INVALID_HEADER",
+            TransErrKind::NoHeader => "E_NO_HEADER",
+            TransErrKind::Store => "E_STORE",
+            TransErrKind::Snapshot => "E_SNAPSHOT",
+            TransErrKind::SerializeClients => "E_EXPORT",
+            TransErrKind::Rejects => "E_REJECTS",
+            TransErrKind::DuplicateTransact => "E_DUPLICATE_TX",
+            TransErrKind::AccountLocked => "E_ACCOUNT_LOCKED",
+            TransErrKind::InsufficientFunds => "E_INSUFFICIENT_FUNDS",
+            TransErrKind::NoClient => "E_NO_CLIENT",
+            TransErrKind::WrongClient => "E_WRONG_CLIENT",
+            TransErrKind::WrongTransState => "E_WRONG_TX_STATE",
+            TransErrKind::ReferNoneExisting => "E_UNKNOWN_TX",
+            TransErrKind::Overflow => "E_OVERFLOW",
+            TransErrKind::Unauthorized => "E_UNAUTHORIZED",
+            TransErrKind::AccountNotLocked => "E_ACCOUNT_NOT_LOCKED",
+            TransErrKind::ShouldBeDeposit => "E_SHOULD_BE_DEPOSIT",
+        }
+    }
+
+    /// The category of this kind of error. Stable, like [`TransErrKind::code`].
+    //
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            TransErrKind::DeserializeCsv
+            | TransErrKind::DeserializeTransact
+            | TransErrKind::DeserializeHeader
+            | TransErrKind::NoHeader => ErrorCategory::Input,
+
+            TransErrKind::InputFile
+            | TransErrKind::Store
+            | TransErrKind::Snapshot
+            | TransErrKind::SerializeClients
+            | TransErrKind::Rejects => ErrorCategory::Io,
+
+            TransErrKind::DuplicateTransact
+            | TransErrKind::AccountLocked
+            | TransErrKind::InsufficientFunds
+            | TransErrKind::NoClient
+            | TransErrKind::WrongClient
+            | TransErrKind::WrongTransState
+            | TransErrKind::ReferNoneExisting
+            | TransErrKind::Overflow
+            | TransErrKind::Unauthorized
+            | TransErrKind::AccountNotLocked
+            | TransErrKind::ShouldBeDeposit => ErrorCategory::BusinessRule,
+        }
+    }
+}
+
+impl TransErr {
+    /// A stable, machine readable code for this error, eg. `E_DUPLICATE_TX`. See [`TransErrKind::code`]
+    /// for the compatibility guarantees. The message is prefixed with it.
+    //
+    pub fn code(&self) -> &'static str {
+        self.kind().code()
+    }
+
+    /// Whether this is an input, business rule or I/O error.
+    //
+    pub fn category(&self) -> ErrorCategory {
+        self.kind().category()
+    }
+
+    /// The variant of this error.
+    //
+    pub fn kind(&self) -> TransErrKind {
+        match self {
+            TransErr::InputFile { .. } => TransErrKind::InputFile,
+            TransErr::DeserializeCsv { .. } => TransErrKind::DeserializeCsv,
+            TransErr::DeserializeTransact { .. } => TransErrKind::DeserializeTransact,
+            TransErr::DeserializeHeader { .. } => TransErrKind::DeserializeHeader,
+            TransErr::NoHeader => TransErrKind::NoHeader,
+            TransErr::Store { .. } => TransErrKind::Store,
+            TransErr::Snapshot { .. } => TransErrKind::Snapshot,
+            TransErr::SerializeClients { .. } => TransErrKind::SerializeClients,
+            TransErr::Rejects { .. } => TransErrKind::Rejects,
+            TransErr::DuplicateTransact { .. } => TransErrKind::DuplicateTransact,
+            TransErr::AccountLocked { .. } => TransErrKind::AccountLocked,
+            TransErr::InsufficientFunds { .. } => TransErrKind::InsufficientFunds,
+            TransErr::NoClient { .. } => TransErrKind::NoClient,
+            TransErr::WrongClient { .. } => TransErrKind::WrongClient,
+            TransErr::WrongTransState { .. } => TransErrKind::WrongTransState,
+            TransErr::ReferNoneExisting { .. } => TransErrKind::ReferNoneExisting,
+            TransErr::Overflow { .. } => TransErrKind::Overflow,
+            TransErr::Unauthorized { .. } => TransErrKind::Unauthorized,
+            TransErr::AccountNotLocked { .. } => TransErrKind::AccountNotLocked,
+            TransErr::ShouldBeDeposit { .. } => TransErrKind::ShouldBeDeposit,
+        }
+    }
+
+    /// Where in the input the transaction that caused this error came from, if known.
+    //
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            TransErr::DeserializeCsv { pos, .. } | TransErr::DeserializeTransact { pos, .. } => {
+                pos.as_deref()
+            }
+
+            _ => self.transact().and_then(|t| t.pos.as_deref()),
+        }
+    }
+
+    /// The transaction that caused this error, for errors raised by the bank.
+    //
+    pub fn transact(&self) -> Option<&Transact> {
+        match self {
+            TransErr::DuplicateTransact { trans }
+            | TransErr::AccountLocked { trans }
+            | TransErr::InsufficientFunds { trans }
+            | TransErr::NoClient { trans }
+            | TransErr::WrongClient { trans }
+            | TransErr::WrongTransState { trans }
+            | TransErr::ReferNoneExisting { trans }
+            | TransErr::Overflow { trans }
+            | TransErr::Unauthorized { trans }
+            | TransErr::AccountNotLocked { trans }
+            | TransErr::ShouldBeDeposit { trans } => Some(trans),
+
+            TransErr::InputFile { .. }
+            | TransErr::Store { .. }
+            | TransErr::Snapshot { .. }
+            | TransErr::SerializeClients { .. }
+            | TransErr::Rejects { .. }
+            | TransErr::DeserializeHeader { .. }
+            | TransErr::DeserializeCsv { .. }
+            | TransErr::DeserializeTransact { .. }
+            | TransErr::NoHeader => None,
+        }
+    }
+
+    // Set the position on errors that relate to a row of input.
+    //
+    pub(crate) fn set_position(&mut self, position: Option<Box<Position>>) {
+        match self {
+            TransErr::DeserializeCsv { pos, .. } | TransErr::DeserializeTransact { pos, .. } => {
+                *pos = position
+            }
+
+            TransErr::DuplicateTransact { trans }
+            | TransErr::AccountLocked { trans }
+            | TransErr::InsufficientFunds { trans }
+            | TransErr::NoClient { trans }
+            | TransErr::WrongClient { trans }
+            | TransErr::WrongTransState { trans }
+            | TransErr::ReferNoneExisting { trans }
+            | TransErr::Overflow { trans }
+            | TransErr::Unauthorized { trans }
+            | TransErr::AccountNotLocked { trans }
+            | TransErr::ShouldBeDeposit { trans } => trans.pos = position,
+
+            _ => {}
+        }
+    }
+}
+
 impl std::error::Error for TransErr {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self {
             TransErr::InputFile { source, .. } => Some(source),
+            TransErr::Store { source, .. } => Some(source),
+            TransErr::Snapshot { source } => Some(source),
             TransErr::SerializeClients { source } => Some(source),
+            TransErr::Rejects { source, .. } => Some(source),
             TransErr::DeserializeHeader { source } => Some(source),
-            TransErr::DeserializeCsv { source } => Some(source),
+            TransErr::DeserializeCsv { source, .. } => Some(source),
 
             TransErr::DeserializeTransact { .. } => None,
             TransErr::DuplicateTransact { .. } => None,
@@ -113,72 +429,170 @@ impl std::error::Error for TransErr {
             TransErr::WrongTransState { .. } => None,
             TransErr::ReferNoneExisting { .. } => None,
             TransErr::ShouldBeDeposit { .. } => None,
+            TransErr::Overflow { .. } => None,
+            TransErr::Unauthorized { .. } => None,
+            TransErr::AccountNotLocked { .. } => None,
             TransErr::NoHeader => None,
         }
     }
 }
 
-impl std::fmt::Display for TransErr {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for TransErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\nError [{}]: ", self.code())?;
+
+        self.fmt_message(f)?;
+
+        match self.position() {
+            Some(pos) => writeln!(f, "At {pos}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Formats a [`TransErr`] on a single line, see [`TransErr::single_line`].
+//
+#[derive(Debug, Clone, Copy)]
+//
+pub struct SingleLine<'a>(&'a TransErr);
+
+impl fmt::Display for SingleLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let err = self.0;
+
+        write!(f, "{} {}: {}", err.code(), err.category(), err.message())?;
+
+        // Quoted rows can span lines, so escape the row.
+        //
+        match err.position() {
+            Some(pos) => write!(
+                f,
+                " At line {} (byte {}): {:?}",
+                pos.line,
+                pos.byte,
+                pos.row_text()
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+impl TransErr {
+    /// Format the error as a single line, `CODE category: message`, followed by the position
+    /// if there is one. Unlike the `Display` implementation, which spreads the message over
+    /// several lines for people, this is meant for logs, one error per line:
+    ///
+    /// ```text
+    /// E_NO_CLIENT business-rule: Cannot withdraw/dispute/resolve/charge back from non-existing client: ... At line 7 (byte 98): "dispute, 2, 1,"
+    /// ```
+    //
+    pub fn single_line(&self) -> SingleLine<'_> {
+        SingleLine(self)
+    }
+
+    // The message without the code and the position, which have their own columns in the
+    // rejected rows, on a single line.
+    //
+    pub(crate) fn message(&self) -> String {
+        struct Message<'a>(&'a TransErr);
+
+        impl fmt::Display for Message<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt_message(f)
+            }
+        }
+
+        Message(self)
+            .to_string()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let no_effect = "\nThis transaction has been ignored and no data was modified by it.";
 
         match &self
 		{
 			TransErr::InputFile{ source, path } =>
 
-				writeln!( f, "\nError: Could not open the supplied input file ({}): {source}", path.to_string_lossy() ),
+				writeln!( f, "Could not open the supplied input file ({}): {source}", path.to_string_lossy() ),
+
+			TransErr::Store{ source, path } =>
+
+				writeln!( f, "Failed to access the transaction store ({}): {source}", path.to_string_lossy() ),
+
+			TransErr::Snapshot{ source } =>
+
+				writeln!( f, "Failed to write or read the bank snapshot: {source}" ),
 
 			TransErr::SerializeClients{ source } =>
 
-				writeln!( f, "\nError: Failed to serialize client information: {source}" ),
+				writeln!( f, "Failed to export client information or the ledger: {source}" ),
+
+			TransErr::Rejects{ source, path } =>
+
+				writeln!( f, "Failed to write the rejected rows ({}): {source}", path.to_string_lossy() ),
 
 			TransErr::DeserializeHeader{ source } =>
 
-				writeln!( f, "\nError: The header could not be deserialized. Underlying error: {source}" ),
+				writeln!( f, "The header could not be deserialized. Underlying error: {source}" ),
 
-			TransErr::DeserializeCsv{source} =>
+			TransErr::DeserializeCsv{source, ..} =>
 
-				write!( f, "\nError: A line of input could not be deserialized into a valid transaction: {source}. {no_effect}" ),
+				writeln!( f, "A line of input could not be deserialized into a valid transaction: {source}. {no_effect}" ),
 
-			TransErr::DeserializeTransact{ kind, record } =>
+			TransErr::DeserializeTransact{ kind, record, .. } =>
 
-				writeln!( f, "\nError: Could not use deserialized data to construct a valid transaction: {kind:?}, {record}. {no_effect}" ),
+				writeln!( f, "Could not use deserialized data to construct a valid transaction: {kind:?}, {record}. {no_effect}" ),
 
 			TransErr::DuplicateTransact{trans} =>
 
-				writeln!( f, "\nError: A duplicate transaction id occurred in your data: {trans}. {no_effect}" ),
+				writeln!( f, "A duplicate transaction id occurred in your data: {trans}. {no_effect}" ),
 
 			TransErr::AccountLocked{trans} =>
 
-				writeln!( f, "\nError: The client account is locked: {trans}. {no_effect}" ),
+				writeln!( f, "The client account is locked: {trans}. {no_effect}" ),
 
 			TransErr::InsufficientFunds{trans} =>
 
-				writeln!( f, "\nError: Cannot withdraw/dispute with insufficient funds: {trans}. {no_effect}" ),
+				writeln!( f, "Cannot withdraw/dispute with insufficient funds: {trans}. {no_effect}" ),
 
 			TransErr::NoClient{trans} =>
 
-				writeln!( f, "\nError: Cannot withdraw/dispute/resolve/charge back from non-existing client: {trans}. {no_effect}" ),
+				writeln!( f, "Cannot withdraw/dispute/resolve/charge back from non-existing client: {trans}. {no_effect}" ),
 
 			TransErr::WrongClient{trans} =>
 
-				writeln!( f, "\nError: Cannot dispute/resolve/charge back from a different client than the original deposit: {trans}. {no_effect}" ),
+				writeln!( f, "Cannot dispute/resolve/charge back from a different client than the original deposit: {trans}. {no_effect}" ),
 
 			TransErr::WrongTransState{trans} =>
 
-				writeln!( f, "\nError: Can only dispute a successful transaction, resolve/charge back a disputed transaction: {trans}. {no_effect}" ),
+				writeln!( f, "Can only dispute a successful transaction, resolve/charge back a disputed transaction: {trans}. {no_effect}" ),
 
 			TransErr::ReferNoneExisting{trans} =>
 
-				writeln!( f, "\nError: Cannot dispute/resolve/charge back a non existing transaction: {trans}. {no_effect}" ),
+				writeln!( f, "Cannot dispute/resolve/charge back a non existing transaction: {trans}. {no_effect}" ),
+
+			TransErr::Overflow{trans} =>
+
+				writeln!( f, "The resulting balance is too large to be represented: {trans}. {no_effect}" ),
+
+			TransErr::Unauthorized{trans} =>
+
+				writeln!( f, "Administrative transactions are only accepted from a privileged source: {trans}. {no_effect}" ),
+
+			TransErr::AccountNotLocked{trans} =>
+
+				writeln!( f, "Cannot unlock an account that isn't locked: {trans}. {no_effect}" ),
 
 			TransErr::ShouldBeDeposit{trans} =>
 
-				writeln!( f, "\nError: Disputed transaction must be a deposit: {trans}. {no_effect}" ),
+				writeln!( f, "Disputed transaction must be a deposit: {trans}. {no_effect}" ),
 
 			TransErr::NoHeader =>
 
-				writeln!( f, "\nError: Only CSV files with a valid header are supported. For a valid header the first line should be: \"type, client, tx, amount\"" ),
+				writeln!( f, "Only CSV files with a valid header are supported. For a valid header the first line should be: \"type, client, tx, amount\"" ),
 		}
     }
 }
@@ -6,28 +6,76 @@ use crate::{import::*, *};
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
 //
 pub enum TransType {
-    Deposit(BigDecimal),
-    WithDraw(BigDecimal),
+    Deposit(Amount),
+    WithDraw(Amount),
     Dispute,
     Resolve,
     ChargeBack,
+
+    /// Administrative: reinstate a locked account, with the reason why.
+    /// Only accepted by [`Bank::process_admin`] and [`Bank::unlock`]. Parsers only read it when
+    /// set up for admin input, see [`CsvParseBuilder::admin`].
+    //
+    Unlock(String),
+}
+
+impl TransType {
+    /// Whether this is an administrative transaction, which may only come from a privileged source.
+    //
+    pub fn is_admin(&self) -> bool {
+        matches!(self, Self::Unlock(_))
+    }
+
+    /// The name of the type as in the `type` column of the input, eg. `withdrawal`. Unlocks are
+    /// called `unlock`, but are only accepted from admin input.
+    //
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Deposit(_) => "deposit",
+            Self::WithDraw(_) => "withdrawal",
+            Self::Dispute => "dispute",
+            Self::Resolve => "resolve",
+            Self::ChargeBack => "chargeback",
+            Self::Unlock(_) => "unlock",
+        }
+    }
+
+    /// The amount of a deposit or withdrawal.
+    //
+    pub fn amount(&self) -> Option<Amount> {
+        match self {
+            Self::Deposit(a) | Self::WithDraw(a) => Some(*a),
+            _ => None,
+        }
+    }
+
+    /// The reason of an unlock.
+    //
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Unlock(reason) => Some(reason),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for TransType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Deposit(a) => write!(f, "Deposit({})", a.normalized()),
-            Self::WithDraw(a) => write!(f, "WithDraw({})", a.normalized()),
+            Self::Deposit(a) => write!(f, "Deposit({a})"),
+            Self::WithDraw(a) => write!(f, "WithDraw({a})"),
             Self::Dispute => write!(f, "Dispute"),
             Self::Resolve => write!(f, "Resolve"),
             Self::ChargeBack => write!(f, "ChargeBack"),
+            Self::Unlock(reason) => write!(f, "Unlock({reason})"),
         }
     }
 }
 
 /// The transaction state.
 //
-#[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
 //
 pub enum TransState {
     /// The transaction has not yet been processed.
@@ -42,15 +90,35 @@ pub enum TransState {
     // database and are just output in error messages.
     //
     // Failed,
-    /// Applies to deposit only, has been disputed. The funds are in "held".
+    /// Applies to deposits and, if enabled on the bank, withdrawals. Has been disputed. The funds are in "held".
     //
     Disputed,
 
-    /// Applies to deposit only, a charge back has been applied to this deposit.
+    /// Applies to deposits and, if enabled on the bank, withdrawals. A charge back has been applied to this
+    /// transaction.
     //
     ChargedBack,
 }
 
+impl TransState {
+    /// The name of the state: `new`, `success`, `disputed` or `charged-back`.
+    //
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransState::New => "new",
+            TransState::Success => "success",
+            TransState::Disputed => "disputed",
+            TransState::ChargedBack => "charged-back",
+        }
+    }
+}
+
+impl fmt::Display for TransState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
 /// Internal representation of a transaction.
 //
 #[allow(missing_docs)]
@@ -61,6 +129,22 @@ pub struct Transact {
     pub state: TransState,
     pub client: u16,
     pub id: u32,
+
+    /// Where in the input this transaction came from, if known. Used for error reporting only,
+    /// the bank does not keep it when storing the transaction.
+    //
+    pub pos: Option<Box<Position>>,
+
+    /// The values of unknown columns in the input, by column name. Only filled when the parser
+    /// passes them through, see [`ExtraColumns`]. Like `pos`, the bank does not store them.
+    //
+    pub extra: Vec<(String, String)>,
+
+    /// The amount held by the dispute of a stored transaction that is [`TransState::Disputed`],
+    /// which can be less than the transaction amount with [`DisputePolicy::Partial`]. Set by the
+    /// store, see [`TransactionStore::set_disputed`]. Zero otherwise.
+    //
+    pub held: Amount,
 }
 
 impl Transact {
@@ -72,12 +156,22 @@ impl Transact {
             client,
             id,
             state: TransState::New,
+            pos: None,
+            extra: Vec::new(),
+            held: Amount::ZERO,
         }
     }
+
+    // Drop what only relates to the input this transaction was read from.
+    //
+    pub(crate) fn clear_input(&mut self) {
+        self.pos = None;
+        self.extra = Vec::new();
+    }
 }
 
 impl fmt::Display for Transact {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "Transaction: type: {}, client: {}, tx: {}",
@@ -92,32 +186,53 @@ impl fmt::Display for Transact {
 #[derive(Debug, Deserialize)]
 //
 pub struct CsvRecord<'a> {
-    r#type: Cow<'a, str>,
-    client: u16,
-    tx: u32,
-    amount: Option<BigDecimal>,
+    pub(crate) r#type: Cow<'a, str>,
+    pub(crate) client: u16,
+    pub(crate) tx: u32,
+    pub(crate) amount: Option<Cow<'a, str>>,
+
+    // Only read for unlocks from admin input.
+    //
+    #[serde(default)]
+    pub(crate) reason: Option<Cow<'a, str>>,
 }
 
-impl CsvRecord<'_> {
+impl<'a> CsvRecord<'a> {
+    /// Create a record as it would be read from a row of the csv input. Usually the parser does
+    /// this, but it is useful to test the conversion to a [`Transact`] on its own.
+    //
+    pub fn new(
+        r#type: impl Into<Cow<'a, str>>,
+        client: u16,
+        tx: u32,
+        amount: Option<Cow<'a, str>>,
+    ) -> Self {
+        Self {
+            r#type: r#type.into(),
+            client,
+            tx,
+            amount,
+            reason: None,
+        }
+    }
+
     fn to_owned(&self) -> CsvRecord<'static> {
         CsvRecord {
             r#type: Cow::Owned(self.r#type.clone().into_owned()),
             client: self.client,
             tx: self.tx,
-            amount: self.amount.clone(),
+            amount: self.amount.clone().map(|a| Cow::Owned(a.into_owned())),
+            reason: self.reason.clone().map(|r| Cow::Owned(r.into_owned())),
         }
     }
 }
 
 impl fmt::Display for CsvRecord<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "CsvRecord: type: {}, client: {}, tx: {}, amount: {:?}",
-            self.r#type,
-            self.client,
-            self.tx,
-            self.amount.as_ref().map(BigDecimal::normalized)
+            self.r#type, self.client, self.tx, self.amount
         )
     }
 }
@@ -126,68 +241,57 @@ impl<'a> TryFrom<CsvRecord<'a>> for Transact {
     type Error = TransErr;
 
     fn try_from(r: CsvRecord<'a>) -> Result<Transact, Self::Error> {
-        let CsvRecord {
-            r#type,
-            client,
-            tx,
-            amount,
-        } = r;
-
-        match (r#type.as_ref(), amount) {
-            (x, Some(a)) => {
-                if a.is_negative() {
-                    let record = CsvRecord {
-                        r#type,
-                        client,
-                        tx,
-                        amount: Some(a),
-                    }
-                    .to_owned();
-                    return Err(TransErr::DeserializeTransact {
-                        kind: DeserTransactKind::AmountNegative,
-                        record,
-                    });
-                }
-
-                let ttype = match x {
-                    "deposit" => TransType::Deposit(a),
-                    "withdrawal" => TransType::WithDraw(a),
-
-                    _ => {
-                        let record = CsvRecord {
-                            r#type,
-                            client,
-                            tx,
-                            amount: Some(a),
-                        }
-                        .to_owned();
-                        return Err(TransErr::DeserializeTransact {
-                            kind: DeserTransactKind::AmountNegative,
-                            record,
-                        });
-                    }
-                };
-
-                Ok(Transact::new(ttype, r.client, r.tx))
-            }
+        Transact::from_record(r, false)
+    }
+}
+
+impl Transact {
+    // Validate a record. Unlocks are only read from admin input, otherwise they are an unknown type.
+    //
+    pub(crate) fn from_record(r: CsvRecord<'_>, admin: bool) -> Result<Transact, TransErr> {
+        let fail = |kind| TransErr::DeserializeTransact {
+            kind,
+            record: r.to_owned(),
+            pos: None,
+        };
+
+        let ttype = match (r.r#type.as_ref(), r.amount.as_deref()) {
+            ("deposit", Some(a)) => TransType::Deposit(parse_amount(a).map_err(fail)?),
+            ("withdrawal", Some(a)) => TransType::WithDraw(parse_amount(a).map_err(fail)?),
+            ("deposit" | "withdrawal", None) => return Err(fail(DeserTransactKind::MissingAmount)),
+
+            ("dispute", None) => TransType::Dispute,
+            ("resolve", None) => TransType::Resolve,
+            ("chargeback", None) => TransType::ChargeBack,
 
-            ("dispute", None) => Ok(Transact::new(TransType::Dispute, r.client, r.tx)),
-            ("resolve", None) => Ok(Transact::new(TransType::Resolve, r.client, r.tx)),
-            ("chargeback", None) => Ok(Transact::new(TransType::ChargeBack, r.client, r.tx)),
-
-            (_, None) => {
-                let record = CsvRecord {
-                    r#type,
-                    client,
-                    tx,
-                    amount: None,
-                }
-                .to_owned();
-                Err(TransErr::DeserializeTransact {
-                    kind: DeserTransactKind::AmountNegative,
-                    record,
-                })
+            ("dispute" | "resolve" | "chargeback", Some(_)) => {
+                return Err(fail(DeserTransactKind::UnexpectedAmount))
             }
+
+            ("unlock", None) if admin => match r.reason.as_deref().map(str::trim) {
+                Some(reason) if !reason.is_empty() => TransType::Unlock(reason.to_string()),
+                _ => return Err(fail(DeserTransactKind::MissingReason)),
+            },
+
+            ("unlock", Some(_)) if admin => return Err(fail(DeserTransactKind::UnexpectedAmount)),
+
+            _ => return Err(fail(DeserTransactKind::UnknownTransType)),
+        };
+
+        Ok(Transact::new(ttype, r.client, r.tx))
+    }
+}
+
+// The amount of a deposit or withdrawal must be a positive number with at most four decimals.
+//
+fn parse_amount(s: &str) -> Result<Amount, DeserTransactKind> {
+    match Amount::from_str(s) {
+        Ok(a) if a.is_negative() => Err(DeserTransactKind::AmountNegative),
+        Ok(a) if a.is_zero() => Err(DeserTransactKind::AmountZero),
+        Ok(a) => Ok(a),
+        Err(ParseAmountError::TooManyDecimals) => Err(DeserTransactKind::TooManyDecimals),
+        Err(ParseAmountError::Invalid | ParseAmountError::Overflow) => {
+            Err(DeserTransactKind::InvalidAmount)
         }
     }
 }
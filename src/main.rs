@@ -1,59 +1,312 @@
 #![forbid(unsafe_code)]
 
 use libtransact::*;
-use std::{path::Path, process::exit};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+const USAGE: &str = "Usage: transact [--state <snapshot file>] [--ledger <ledger file>] \
+                     [--rejects <rejects file>] [--input-format csv|jsonl] \
+                     [--format csv|json|ndjson] <transactions.csv>";
+
+/// The format of the input file.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//
+enum InputFormat {
+    Csv,
+    JsonLines,
+}
+
+impl InputFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(InputFormat::Csv),
+            "jsonl" | "ndjson" => Some(InputFormat::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// JSON Lines for `.jsonl` and `.ndjson` files, CSV for anything else.
+    //
+    fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| Self::from_name(&ext.to_ascii_lowercase()))
+            .unwrap_or(InputFormat::Csv)
+    }
+}
+
+/// The format of the client data written to stdout.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//
+enum OutputFormat {
+    Csv,
+    Json(JsonStyle),
+}
+
+impl OutputFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(OutputFormat::Csv),
+            "json" => Some(OutputFormat::Json(JsonStyle::Array)),
+            "ndjson" | "jsonl" => Some(OutputFormat::Json(JsonStyle::Lines)),
+            _ => None,
+        }
+    }
+}
+
+/// The command line arguments.
+//
+#[derive(Debug)]
+//
+struct Args {
+    input: PathBuf,
+    state: Option<PathBuf>,
+    ledger: Option<PathBuf>,
+    rejects: Option<PathBuf>,
+    input_format: InputFormat,
+    output_format: OutputFormat,
+}
 
 /// Simple CLI frontend which will collect the first argument as a filename. The file is assumed
-/// to be transactions encoded in CSV with comma separated values.
+/// to be transactions encoded in CSV with comma separated values, or in JSON Lines if the file
+/// name ends in `.jsonl` or `.ndjson`. `--input-format csv|jsonl` overrides the extension.
+///
+/// The client data is written to stdout as CSV, or with `--format json` or `--format ndjson` as
+/// a JSON array or one JSON object per line.
+///
+/// With `--state <file>`, the bank is restored from the snapshot in that file if it exists and
+/// the new state is written back to it after processing. This allows processing files
+/// incrementally, eg. one per day.
+///
+/// With `--ledger <file>`, all stored transactions are written to that file with their final
+/// state, as JSON if the name ends in `.json`, `.ndjson` or `.jsonl` and CSV otherwise.
+///
+/// With `--rejects <file>`, the rejected rows are also written to that file as CSV, with the
+/// columns of the input followed by `error_code`, `line` and `message`, so they can be sent back
+/// to the partner.
 ///
 /// Erroneous transactions will be skipped and errors will be printed to stderr with the status
 /// code representing how many errors occurred.
 //
 fn main() {
-    // first argument is the process path.
+    let Args {
+        input,
+        state,
+        ledger,
+        rejects,
+        input_format,
+        output_format,
+    } = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+
+        Err(msg) => {
+            eprintln!("Error: {msg}\n{USAGE}");
+            exit(1);
+        }
+    };
+
+    // Rejected csv rows are written in the columns of the input.
     //
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let transactions: Result<(Box<dyn Iterator<Item = _>>, _), _> = match input_format {
+        InputFormat::Csv => CsvParse::try_from(input.as_path()).map(|p| {
+            let layout = p.reject_layout().clone();
+            (Box::new(p) as _, layout)
+        }),
 
-    if args.len() != 1 {
-        eprintln!( "Error: The transact takes exactly one argument. A path to a CSV file with transaction. Got {} arguments.", args.len() );
-        exit(1);
-    }
+        InputFormat::JsonLines => JsonLinesParse::try_from(input.as_path())
+            .map(|p| (Box::new(p) as _, RejectLayout::new())),
+    };
 
-    let transactions = match CsvParse::try_from(Path::new(&args[0])) {
-        Ok(parser) => parser,
+    let (transactions, reject_layout) = match transactions {
+        Ok(parsed) => parsed,
 
         Err(e) => {
-            eprintln!("{e}");
+            eprintln!("{}", e.single_line());
             exit(1);
         }
     };
 
-    let mut bank = Bank::new();
+    let mut bank = match &state {
+        Some(path) if path.exists() => match restore(path) {
+            Ok(bank) => bank,
+
+            Err(e) => {
+                eprintln!("{}", e.single_line());
+                exit(1);
+            }
+        },
 
-    let errors = bank.process(transactions);
+        _ => Bank::new(),
+    };
 
-    // report errors on stderr.
+    // report errors on stderr as they happen.
     //
-    errors.iter().for_each(|e| eprint!("{}", e));
+    let mut stderr = WriteSink::new(std::io::stderr().lock());
+    stderr.set_single_line(true);
 
-    let num_err = errors.len() as i32;
+    let report = match &rejects {
+        Some(path) => {
+            let file = match File::create(path) {
+                Ok(file) => file,
 
-    // report results on stdout.
-    //
-    let out = match CsvExport::export(bank.clients()) {
-        Ok(out) => out,
+                Err(source) => {
+                    let path = path.clone();
+                    eprintln!("{}", TransErr::Rejects { source, path }.single_line());
+                    exit(1);
+                }
+            };
 
-        Err(e) => {
-            eprintln!("{e}");
-            exit(num_err + 1);
+            let mut sink = RejectSink::with_layout(file, &reject_layout, stderr);
+            let report = bank.process_into(transactions, &mut sink);
+
+            if let Err(source) = sink.finish() {
+                let path = path.clone();
+                eprintln!("{}", TransErr::Rejects { source, path }.single_line());
+                exit(report.failed as i32 + 1);
+            }
+
+            report
         }
+
+        None => bank.process_into(transactions, stderr),
     };
 
-    // happy path
+    let num_err = report.failed as i32;
+
+    if let Some(path) = &state {
+        if let Err(e) = save(&bank, path) {
+            eprintln!("{}", e.single_line());
+            exit(num_err + 1);
+        }
+    }
+
+    if let Some(path) = &ledger {
+        if let Err(e) = write_ledger(&bank, path) {
+            eprintln!("{}", e.single_line());
+            exit(num_err + 1);
+        }
+    }
+
+    // report results on stdout.
     //
-    print!("{}", out);
+    let out = std::io::stdout().lock();
+
+    let exported = match output_format {
+        OutputFormat::Csv => CsvExport::write_to(bank.clients(), out),
+        OutputFormat::Json(style) => JsonExport::write_to(bank.clients(), style, out),
+    };
+
+    if let Err(e) = exported {
+        eprintln!("{}", e.single_line());
+        exit(num_err + 1);
+    }
 
     // report the number of errors in the status code.
     //
     exit(num_err);
 }
+
+/// Returns the input file, the optional state, ledger and rejects files and the formats of the
+/// input and output.
+//
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut input = None;
+    let mut state = None;
+    let mut ledger = None;
+    let mut rejects = None;
+    let mut input_format = None;
+    let mut output_format = OutputFormat::Csv;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--state" => match args.next() {
+                Some(path) => state = Some(PathBuf::from(path)),
+                None => return Err("--state requires a path.".to_string()),
+            },
+
+            "--ledger" => match args.next() {
+                Some(path) => ledger = Some(PathBuf::from(path)),
+                None => return Err("--ledger requires a path.".to_string()),
+            },
+
+            "--rejects" => match args.next() {
+                Some(path) => rejects = Some(PathBuf::from(path)),
+                None => return Err("--rejects requires a path.".to_string()),
+            },
+
+            "--input-format" => match args.next().as_deref().map(InputFormat::from_name) {
+                Some(Some(f)) => input_format = Some(f),
+                _ => return Err("--input-format requires csv or jsonl.".to_string()),
+            },
+
+            "--format" => match args.next().as_deref().map(OutputFormat::from_name) {
+                Some(Some(f)) => output_format = f,
+                _ => return Err("--format requires csv, json or ndjson.".to_string()),
+            },
+
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+
+            _ => return Err(format!("Unexpected argument: {arg}.")),
+        }
+    }
+
+    match input {
+        Some(input) => Ok(Args {
+            input_format: input_format.unwrap_or_else(|| InputFormat::from_path(&input)),
+            output_format,
+            input,
+            state,
+            ledger,
+            rejects,
+        }),
+
+        None => Err("A path to a file with transactions is required.".to_string()),
+    }
+}
+
+fn restore(path: &Path) -> Result<Bank, TransErr> {
+    let file = File::open(path).map_err(|source| TransErr::InputFile {
+        source,
+        path: path.to_path_buf(),
+    })?;
+
+    Bank::restore(file)
+}
+
+/// Write the snapshot to a temporary file first, so a crash never leaves a half written state file.
+//
+fn save(bank: &Bank, path: &Path) -> Result<(), TransErr> {
+    let tmp = path.with_extension("tmp");
+
+    let file = File::create(&tmp).map_err(|source| TransErr::Snapshot { source })?;
+
+    bank.snapshot(&file)?;
+
+    file.sync_all()
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|source| TransErr::Snapshot { source })
+}
+
+/// Write the stored transactions with their state, in the format given by the extension.
+//
+fn write_ledger(bank: &Bank, path: &Path) -> Result<(), TransErr> {
+    let file = File::create(path).map_err(|source| TransErr::SerializeClients { source })?;
+
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    match ext.as_deref() {
+        Some("json") => LedgerExport::write_json(bank.transactions(), JsonStyle::Array, file),
+        Some("ndjson" | "jsonl") => {
+            LedgerExport::write_json(bank.transactions(), JsonStyle::Lines, file)
+        }
+        _ => LedgerExport::write_csv(bank.transactions(), file),
+    }
+}
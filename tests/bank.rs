@@ -7,10 +7,13 @@
 //! ✓ dispute
 //! ✓ resolve dispute
 //! ✓ chargeback dispute
+//! ✓ resolve and chargeback open disputes on a locked account, if enabled
 //! ✓ take input from file
 //! ✓ run binary
 //! ✓ Test a large number of operation to see when rounding errors appear.
 //
+#![cfg(feature = "csv")]
+
 mod common;
 
 use {
@@ -36,7 +39,7 @@ fn two_clients() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0);
 
@@ -69,7 +72,7 @@ fn dispute() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0);
 
@@ -97,7 +100,7 @@ fn resolve() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -125,7 +128,7 @@ fn chargeback() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -138,6 +141,84 @@ fn chargeback() -> DynResult {
     Ok(())
 }
 
+// Two disputed deposits, the first one is charged back which locks the account.
+//
+const LOCKED_WITH_OPEN_DISPUTE: &str = "
+
+	      type, client, tx, amount
+	   deposit,      1,  1,    1.0
+	   deposit,      1,  2,    2.0
+	   deposit,      1,  3,    4.0
+	   dispute,      1,  1,
+	   dispute,      1,  2,
+	chargeback,      1,  1,
+
+";
+
+#[test]
+fn locked_resolve_open_dispute() -> DynResult {
+    let mut bank = Bank::new();
+    bank.set_locked_resolutions(true);
+
+    let err = bank
+        .process(CsvParse::try_from(LOCKED_WITH_OPEN_DISPUTE)?)
+        .errors;
+    assert_eq!(err.len(), 0, "{err:?}");
+
+    let input = "
+
+		      type, client, tx, amount
+		   resolve,      1,  2,
+		   deposit,      1,  4,    1.0
+		withdrawal,      1,  5,    1.0
+		   dispute,      1,  3,
+
+	";
+
+    let err = bank.process(CsvParse::try_from(input)?).errors;
+
+    // Deposits, withdrawals and new disputes remain blocked.
+    //
+    assert_eq!(err.len(), 3, "{err:?}");
+    assert!(err
+        .iter()
+        .all(|e| matches!(e, TransErr::AccountLocked { .. })));
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("6.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("6.0"));
+    assert!(client.is_locked());
+
+    Ok(())
+}
+
+#[test]
+fn locked_chargeback_open_dispute() -> DynResult {
+    let mut bank = Bank::new();
+    bank.set_locked_resolutions(true);
+
+    let err = bank
+        .process(CsvParse::try_from(LOCKED_WITH_OPEN_DISPUTE)?)
+        .errors;
+    assert_eq!(err.len(), 0, "{err:?}");
+
+    let input = "type, client, tx, amount\nchargeback, 1, 2,\n";
+    let err = bank.process(CsvParse::new(input.as_bytes())?).errors;
+
+    assert_eq!(err.len(), 0, "{err:?}");
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("4.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("4.0"));
+    assert!(client.is_locked());
+
+    Ok(())
+}
+
 // Test a large number of operation to see when rounding errors appear.
 //
 #[test]
@@ -155,7 +236,7 @@ fn precision() -> DynResult {
     let parser = CsvParse::new(Cursor::new(input))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -185,7 +266,7 @@ fn precision_9999() -> DynResult {
     let parser = CsvParse::new(Cursor::new(input))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
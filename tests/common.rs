@@ -1,9 +1,10 @@
-use {bigdecimal::BigDecimal, std::str::FromStr};
+use {libtransact::Amount, std::str::FromStr};
 
 pub type DynResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
-/// Shorthand for creating BigDecimal.
+/// Shorthand for creating an Amount.
 //
-pub fn dec(s: &str) -> BigDecimal {
-    BigDecimal::from_str(s).unwrap()
+#[allow(dead_code)] // not every test file uses it.
+pub fn dec(s: &str) -> Amount {
+    Amount::from_str(s).unwrap()
 }
@@ -0,0 +1,299 @@
+//! Tests for configuring the csv dialect with CsvParseBuilder.
+//!
+//! Tested:
+//!
+//! ✓ tab, semicolon and pipe delimiters
+//! ✓ other quote character
+//! ✓ quoting disabled
+//! ✓ comments
+//! ✓ blank lines reported instead of skipped, also before the header and with \r\n
+//! ✓ blank lines in quoted fields are not reported
+//! ✓ byte order mark kept
+//! ✓ rows longer than the maximum end the input
+//! ✓ stray quotes in unquoted fields don't hide row ends from max_row_len or blank lines
+//! ✓ from_path
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{fmt::Write, path::Path},
+};
+
+// The type, client and tx of transactions, or the kind and line of errors.
+//
+fn summary(parser: CsvParse<&[u8]>) -> Vec<Result<(u16, u32), (TransErrKind, u64)>> {
+    parser
+        .map(|r| match r {
+            Ok(t) => Ok((t.client, t.id)),
+            Err(e) => Err((e.kind(), e.position().map_or(0, |p| p.line))),
+        })
+        .collect()
+}
+
+#[test]
+fn delimiters() -> DynResult {
+    for delimiter in [b'\t', b';', b'|'] {
+        let d = delimiter as char;
+        let input =
+            format!("type{d}client{d}tx{d}amount\ndeposit{d}1{d}1{d}1.5\ndispute{d}1{d}1{d}\n");
+
+        let parser = CsvParseBuilder::new()
+            .delimiter(delimiter)
+            .from_reader(input.as_bytes())?;
+
+        let trans: Vec<_> = parser.collect::<Result<_, _>>()?;
+
+        assert_eq!(trans.len(), 2);
+        assert_eq!(trans[0].ttype, TransType::Deposit(dec("1.5")));
+        assert_eq!(trans[1].ttype, TransType::Dispute);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn quotes() -> DynResult {
+    let input = "type,client,tx,amount\n'deposit','1',1,'1,5'\n'withdrawal',1,2,0.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .quote(b'\'')
+        .from_reader(input.as_bytes())?;
+
+    let results: Vec<_> = parser.collect();
+
+    // 1,5 is a single field now, which is not a valid amount.
+    //
+    assert!(matches!(
+        results[0],
+        Err(TransErr::DeserializeTransact {
+            kind: DeserTransactKind::InvalidAmount,
+            ..
+        })
+    ));
+
+    assert_eq!(results[1].as_ref().unwrap().id, 2);
+
+    // Without quoting the quotes are part of the field.
+    //
+    let input = "type,client,tx,amount\n\"deposit\",1,1,1.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .quoting(false)
+        .from_reader(input.as_bytes())?;
+
+    assert!(matches!(
+        summary(parser)[..],
+        [Err((TransErrKind::DeserializeTransact, 2))]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn comments() -> DynResult {
+    let input = "#generated on monday\ntype,client,tx,amount\ndeposit,1,1,1.5\n# deposit,1,2,1.5\ndeposit,1,3,1.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .comment(Some(b'#'))
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(summary(parser), vec![Ok((1, 1)), Ok((1, 3))]);
+
+    Ok(())
+}
+
+#[test]
+fn blank_lines() -> DynResult {
+    let input = "\ntype,client,tx,amount\ndeposit,1,1,1.5\n\n\ndeposit,1,2,1.5\n\n";
+
+    let blank = |line| Err((TransErrKind::DeserializeCsv, line));
+
+    let parser = CsvParseBuilder::new()
+        .skip_blank_lines(false)
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(
+        summary(parser),
+        vec![
+            blank(1),
+            Ok((1, 1)),
+            blank(4),
+            blank(5),
+            Ok((1, 2)),
+            blank(7)
+        ]
+    );
+
+    // By default they are skipped.
+    //
+    assert_eq!(
+        summary(CsvParse::new(input.as_bytes())?),
+        vec![Ok((1, 1)), Ok((1, 2))]
+    );
+
+    // \r\n line endings.
+    //
+    let input = "type,client,tx,amount\r\ndeposit,1,1,1.5\r\n\r\ndeposit,1,2,1.5\r\n";
+
+    let parser = CsvParseBuilder::new()
+        .skip_blank_lines(false)
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(summary(parser), vec![Ok((1, 1)), blank(3), Ok((1, 2))]);
+
+    // A blank line in a quoted field is part of the field.
+    //
+    let input = "type,client,tx,amount\n\"deposit\n\n\",1,1,1.5\ndeposit,1,2,1.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .skip_blank_lines(false)
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(summary(parser), vec![Ok((1, 1)), Ok((1, 2))]);
+
+    Ok(())
+}
+
+#[test]
+fn bom() -> DynResult {
+    let input = "\u{feff}type,client,tx,amount\ndeposit,1,1,1.5\n";
+
+    assert_eq!(summary(CsvParse::new(input.as_bytes())?), vec![Ok((1, 1))]);
+
+    let parser = CsvParseBuilder::new()
+        .strip_bom(false)
+        .from_reader(input.as_bytes());
+
+    assert!(matches!(parser, Err(TransErr::NoHeader)));
+
+    // Without header the byte order mark ends up in the type.
+    //
+    let layout = CsvLayout::headerless([Column::Type, Column::Client, Column::Tx, Column::Amount]);
+    let input = "\u{feff}deposit,1,1,1.5\ndeposit,1,2,1.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .strip_bom(false)
+        .layout(layout)
+        .from_reader(input.as_bytes())?;
+
+    let results: Vec<_> = parser.collect();
+
+    assert!(matches!(
+        results[0],
+        Err(TransErr::DeserializeTransact {
+            kind: DeserTransactKind::UnknownTransType,
+            ..
+        })
+    ));
+
+    assert_eq!(
+        results[0].as_ref().unwrap_err().position().map(|p| p.line),
+        Some(1)
+    );
+    assert_eq!(results[1].as_ref().unwrap().id, 2);
+
+    Ok(())
+}
+
+#[test]
+fn max_row_len() -> DynResult {
+    // Enough rows to go beyond the buffer of the csv reader.
+    //
+    let mut input = String::from("type,client,tx,amount\n");
+
+    for tx in 1..=1000 {
+        writeln!(input, "deposit,1,{tx},1.5")?;
+    }
+
+    writeln!(input, "deposit,1,1001,1.{}", "0".repeat(100))?;
+    writeln!(input, "deposit,1,1002,1.5")?;
+
+    let results = summary(
+        CsvParseBuilder::new()
+            .max_row_len(Some(40))
+            .from_reader(input.as_bytes())?,
+    );
+
+    assert_eq!(results.len(), 1001);
+    assert!(results[..1000].iter().all(Result::is_ok));
+    assert_eq!(results[1000], Err((TransErrKind::DeserializeCsv, 1002)));
+
+    // The header counts as well.
+    //
+    let parser = CsvParseBuilder::new()
+        .max_row_len(Some(10))
+        .from_reader(input.as_bytes());
+
+    assert!(matches!(parser, Err(TransErr::DeserializeHeader { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn stray_quote_max_row_len() -> DynResult {
+    let input = "type,client,tx,amount\n\
+                 deposit,1,1,5\"\n\
+                 deposit,1,2,1.5\n\
+                 \"de\"\"posit\",1,3,1\n\
+                 deposit,1,4,1.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .max_row_len(Some(25))
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(
+        summary(parser),
+        vec![
+            Err((TransErrKind::DeserializeTransact, 2)),
+            Ok((1, 2)),
+            Err((TransErrKind::DeserializeTransact, 4)),
+            Ok((1, 4)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stray_quote_blank_lines() -> DynResult {
+    let input = "type,client,tx,amount\ndep\"osit,1,1,5\n\ndeposit,1,2,1.5\n";
+
+    let parser = CsvParseBuilder::new()
+        .skip_blank_lines(false)
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(
+        summary(parser),
+        vec![
+            Err((TransErrKind::DeserializeTransact, 2)),
+            Err((TransErrKind::DeserializeCsv, 3)),
+            Ok((1, 2)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn from_path() -> DynResult {
+    let parser = CsvParseBuilder::new()
+        .skip_blank_lines(false)
+        .from_path(Path::new("tests/data/empty_middle.csv"))?;
+
+    let mut bank = Bank::new();
+    let report = bank.process(parser);
+
+    assert!(report
+        .errors
+        .iter()
+        .all(|e| e.kind() == TransErrKind::DeserializeCsv));
+    assert!(!report.errors.is_empty());
+    assert_eq!(report.succeeded, 5);
+
+    Ok(())
+}
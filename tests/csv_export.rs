@@ -0,0 +1,268 @@
+//! Tests for exporting the client data to CSV.
+//!
+//! Tested:
+//!
+//! ✓ write_to gives the same output as export
+//! ✓ errors writing the output are returned as SerializeClients
+//! ✓ sorted by client id by default, by total or locked status and descending
+//! ✓ selected and reordered columns
+//! ✓ compact output
+//! ✓ fixed decimals with rounding
+//! ✓ any account store, also behind a trait object
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{io, path::Path},
+};
+
+// Accepts `limit` bytes, then fails.
+//
+struct Full {
+    limit: usize,
+}
+
+impl io::Write for Full {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.limit == 0 {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+        }
+
+        let n = buf.len().min(self.limit);
+        self.limit -= n;
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Client 3 is locked and has nothing left, the others are not locked.
+//
+fn bank() -> DynResult<Bank> {
+    let input = "
+		type,       client, tx, amount
+		deposit,         3,  1,    5.0
+		deposit,         1,  2,  2.125
+		deposit,         2,  3,   0.25
+		deposit,         4,  4,  2.135
+		dispute,         3,  1,
+		chargeback,      3,  1,
+		deposit,         3,  5,    1.0
+	";
+
+    let mut bank = Bank::new();
+    let errs = bank.process(CsvParse::try_from(input)?).errors;
+
+    assert_eq!(errs.len(), 1, "{errs:?}");
+
+    Ok(bank)
+}
+
+// The first value of each row.
+//
+fn ids(out: &str) -> Vec<&str> {
+    out.lines()
+        .skip(1)
+        .map(|l| l.split(',').next().unwrap().trim())
+        .collect()
+}
+
+#[test]
+fn write_to() -> DynResult {
+    let mut bank = Bank::new();
+    bank.process(CsvParse::try_from(Path::new("tests/data/simple.csv"))?);
+
+    let mut out = Vec::new();
+    CsvExport::write_to(bank.clients(), &mut out)?;
+
+    let out = String::from_utf8(out)?;
+
+    assert_eq!(out, CsvExport::export(bank.clients())?);
+    assert_eq!(out.lines().count(), 3);
+    assert!(out.contains("2,        1.9,          0,        1.9,      false"));
+
+    Ok(())
+}
+
+#[test]
+fn write_error() -> DynResult {
+    let mut bank = Bank::new();
+    bank.process(CsvParse::try_from(Path::new("tests/data/simple.csv"))?);
+
+    for limit in [0, 10, 100] {
+        let err = CsvExport::write_to(bank.clients(), Full { limit }).unwrap_err();
+
+        assert_eq!(err.kind(), TransErrKind::SerializeClients);
+
+        match err {
+            TransErr::SerializeClients { source } => {
+                assert_eq!(source.kind(), io::ErrorKind::BrokenPipe)
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sorted() -> DynResult {
+    let bank = bank()?;
+    let export = |options: &ExportOptions| CsvExport::export_with(bank.clients(), options);
+
+    let out = CsvExport::export(bank.clients())?;
+
+    assert_eq!(ids(&out), ["1", "2", "3", "4"]);
+    assert_eq!(out, export(&ExportOptions::new())?);
+
+    let mut options = ExportOptions::new();
+
+    assert_eq!(
+        ids(&export(options.sort_by(SortBy::Total))?),
+        ["3", "2", "1", "4"]
+    );
+    assert_eq!(
+        ids(&export(options.descending(true))?),
+        ["4", "1", "2", "3"]
+    );
+    assert_eq!(
+        ids(&export(options.sort_by(SortBy::Locked))?),
+        ["3", "4", "2", "1"]
+    );
+    assert_eq!(
+        ids(&export(options.descending(false))?),
+        ["1", "2", "4", "3"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn columns() -> DynResult {
+    let bank = bank()?;
+
+    let mut options = ExportOptions::new();
+    options.columns([ClientColumn::Locked, ClientColumn::Client]);
+
+    let out = CsvExport::export_with(bank.clients(), &options)?;
+
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        [
+            "     locked,      client",
+            "      false,          1",
+            "      false,          2",
+            "       true,          3",
+            "      false,          4",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn compact() -> DynResult {
+    let bank = bank()?;
+
+    let mut options = ExportOptions::new();
+    options.padded(false);
+
+    let out = CsvExport::export_with(bank.clients(), &options)?;
+
+    assert_eq!(
+        out,
+        "client,available,held,total,locked\n\
+         1,2.125,0,2.125,false\n\
+         2,0.25,0,0.25,false\n\
+         3,0,0,0,true\n\
+         4,2.135,0,2.135,false\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn decimals() -> DynResult {
+    let bank = bank()?;
+
+    let mut options = ExportOptions::new();
+    options
+        .padded(false)
+        .columns([ClientColumn::Client, ClientColumn::Total])
+        .decimals(Some(2));
+
+    let export = |options: &ExportOptions| CsvExport::export_with(bank.clients(), options);
+
+    assert_eq!(
+        export(&options)?,
+        "client,total\n1,2.12\n2,0.25\n3,0.00\n4,2.14\n"
+    );
+
+    assert_eq!(
+        export(options.rounding(Rounding::HalfUp))?,
+        "client,total\n1,2.13\n2,0.25\n3,0.00\n4,2.14\n"
+    );
+
+    assert_eq!(
+        export(options.rounding(Rounding::TowardZero).decimals(Some(1)))?,
+        "client,total\n1,2.1\n2,0.2\n3,0.0\n4,2.1\n"
+    );
+
+    Ok(())
+}
+
+// An account store that isn't a HashMap.
+//
+struct Accounts(Vec<Client>);
+
+impl AccountStore for Accounts {
+    fn get(&self, id: u16) -> Option<&Client> {
+        self.0.iter().find(|c| c.id() == id)
+    }
+
+    fn get_mut(&mut self, id: u16) -> Option<&mut Client> {
+        self.0.iter_mut().find(|c| c.id() == id)
+    }
+
+    fn get_or_create(&mut self, id: u16) -> &mut Client {
+        match self.0.iter().position(|c| c.id() == id) {
+            Some(i) => &mut self.0[i],
+
+            None => {
+                self.0.push(Client::new(id));
+                self.0.last_mut().unwrap()
+            }
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Client> + '_> {
+        Box::new(self.0.iter())
+    }
+}
+
+#[test]
+fn account_store() -> DynResult {
+    let mut accounts = Accounts(Vec::new());
+    accounts.get_or_create(2);
+    accounts.get_or_create(1).lock();
+
+    let mut options = ExportOptions::new();
+    options.padded(false);
+
+    let expected = "client,available,held,total,locked\n1,0,0,0,true\n2,0,0,0,false\n";
+
+    assert_eq!(CsvExport::export_with(&accounts, &options)?, expected);
+
+    let store: &dyn AccountStore = &accounts;
+    assert_eq!(CsvExport::export_with(store, &options)?, expected);
+
+    Ok(())
+}
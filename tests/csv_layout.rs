@@ -0,0 +1,238 @@
+//! Tests for the layout of the columns in csv input.
+//!
+//! Tested:
+//!
+//! ✓ columns in any order
+//! ✓ errors in reordered columns point at the field in the input
+//! ✓ case-insensitive header names
+//! ✓ headerless input with a configured order
+//! ✓ extra columns are ignored
+//! ✓ extra columns are passed through, with and without header
+//! ✓ missing and duplicate columns are rejected
+//! ✓ headerless rows with the wrong number of fields
+//! ✓ extra columns are not stored by the bank
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq, std::fs};
+
+fn transactions(parser: CsvParse<&[u8]>) -> Vec<(u16, u32, TransType)> {
+    parser
+        .filter_map(Result::ok)
+        .map(|t| (t.client, t.id, t.ttype))
+        .collect()
+}
+
+fn expected() -> Vec<(u16, u32, TransType)> {
+    vec![
+        (1, 1, TransType::Deposit(dec("1.5"))),
+        (1, 2, TransType::WithDraw(dec("0.5"))),
+        (1, 1, TransType::Dispute),
+    ]
+}
+
+#[test]
+fn reordered() -> DynResult {
+    let input = "
+		tx, amount, client, type
+		 1,    1.5,      1, deposit
+		 2,    0.5,      1, withdrawal
+		 1,       ,      1, dispute
+	";
+
+    assert_eq!(transactions(CsvParse::try_from(input)?), expected());
+
+    Ok(())
+}
+
+#[test]
+fn reordered_error_field() -> DynResult {
+    let input = "
+		tx, note, amount, client, type
+		 1,   hi,    1.5,    one, deposit
+	";
+
+    let err = CsvParse::try_from(input)?.next().unwrap().unwrap_err();
+
+    let field = match &err {
+        TransErr::DeserializeCsv { source, .. } => match source.csv().map(csv::Error::kind) {
+            Some(csv::ErrorKind::Deserialize { err, .. }) => err.field(),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    assert_eq!(field, Some(3), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn case_insensitive() -> DynResult {
+    let input = "
+		Type, CLIENT, Tx, Amount
+		deposit,     1,  1,  1.5
+		withdrawal,  1,  2,  0.5
+		dispute,     1,  1,
+	";
+
+    assert_eq!(transactions(CsvParse::try_from(input)?), expected());
+
+    Ok(())
+}
+
+#[test]
+fn headerless() -> DynResult {
+    let layout = CsvLayout::headerless([Column::Type, Column::Client, Column::Tx, Column::Amount]);
+    let data = fs::read("tests/data/no_headers.csv")?;
+
+    let mut bank = Bank::new();
+    let errs = bank
+        .process(CsvParse::with_layout(data.as_slice(), &layout)?)
+        .errors;
+
+    assert!(errs.is_empty(), "{errs:?}");
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("1.5"));
+    assert_eq!(bank.clients().get(&2).unwrap().total(), dec("1.9"));
+
+    // The first row is a transaction, so it has a position.
+    //
+    let layout = CsvLayout::headerless([Column::Client, Column::Tx, Column::Type, Column::Amount]);
+    let first = CsvParse::with_layout("1, 1, deposit, 1.5".as_bytes(), &layout)?
+        .next()
+        .unwrap()?;
+
+    assert_eq!(first.ttype, TransType::Deposit(dec("1.5")));
+    assert_eq!(first.pos.map(|p| p.line), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn extra_ignored() -> DynResult {
+    let input = "
+		type, note, client, tx, amount, branch
+		deposit,    a,  1,  1,  1.5,   x
+		withdrawal, b,  1,  2,  0.5,   y
+		dispute,    c,  1,  1,     ,   z
+	";
+
+    let parser = CsvParse::try_from(input)?;
+
+    assert_eq!(transactions(parser), expected());
+
+    // The position still has the whole row.
+    //
+    let first = CsvParse::try_from(input)?.next().unwrap()?;
+
+    assert!(first.extra.is_empty());
+    assert_eq!(
+        first.pos.unwrap().row_text(),
+        "\t\tdeposit,    a,  1,  1,  1.5,   x"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn extra_passed_through() -> DynResult {
+    let input = "
+		type, note, client, tx, amount
+		deposit,    first,  1,  1,  1.5
+		withdrawal,      ,  1,  2,  0.5
+	";
+
+    let mut layout = CsvLayout::new();
+    layout.set_extra_columns(ExtraColumns::PassThrough);
+
+    let extra: Vec<_> = CsvParse::with_layout(input.trim().as_bytes(), &layout)?
+        .map(|t| t.map(|t| t.extra))
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(
+        extra,
+        vec![
+            vec![("note".to_string(), "first".to_string())],
+            vec![("note".to_string(), String::new())],
+        ]
+    );
+
+    // Without header.
+    //
+    let mut layout = CsvLayout::headerless([
+        Column::Type,
+        Column::Client,
+        Column::Tx,
+        Column::Amount,
+        Column::Extra("note".to_string()),
+    ]);
+
+    layout.set_extra_columns(ExtraColumns::PassThrough);
+
+    let trans = CsvParse::with_layout("deposit, 1, 1, 1.5, hello".as_bytes(), &layout)?
+        .next()
+        .unwrap()?;
+
+    assert_eq!(trans.extra, vec![("note".to_string(), "hello".to_string())]);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_columns() {
+    let no_header =
+        |input: &'static str| matches!(CsvParse::try_from(input), Err(TransErr::NoHeader));
+
+    assert!(no_header("type, client, tx\ndeposit, 1, 1"));
+    assert!(no_header(
+        "type, client, tx, amount, TX\ndeposit, 1, 1, 1.0, 2"
+    ));
+    assert!(no_header("kind, client, tx, amount\ndeposit, 1, 1, 1.0"));
+
+    let layout = CsvLayout::headerless([Column::Type, Column::Client, Column::Tx]);
+
+    assert!(matches!(
+        CsvParse::with_layout("deposit, 1, 1".as_bytes(), &layout),
+        Err(TransErr::NoHeader)
+    ));
+}
+
+#[test]
+fn headerless_field_count() -> DynResult {
+    let layout = CsvLayout::headerless([Column::Type, Column::Client, Column::Tx, Column::Amount]);
+    let input = "deposit, 1, 1\ndeposit, 1, 2";
+
+    let results: Vec<_> = CsvParse::with_layout(input.as_bytes(), &layout)?.collect();
+
+    assert_eq!(results.len(), 2);
+
+    for (i, result) in results.iter().enumerate() {
+        let err = result.as_ref().unwrap_err();
+
+        assert!(matches!(err, TransErr::DeserializeCsv { .. }), "{err}");
+        assert_eq!(err.position().map(|p| p.line), Some(i as u64 + 1));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn not_stored() -> DynResult {
+    let input = "type, client, tx, amount, note\ndeposit, 1, 1, 1.5, hello";
+
+    let mut layout = CsvLayout::new();
+    layout.set_extra_columns(ExtraColumns::PassThrough);
+
+    let mut bank = Bank::new();
+    let mut receipts = bank.process_iter(CsvParse::with_layout(input.as_bytes(), &layout)?);
+
+    let receipt = receipts.next().unwrap()?;
+    drop(receipts);
+
+    assert_eq!(receipt.trans.extra.len(), 1);
+    assert!(bank.transactions().get(&1).unwrap().extra.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Tests for the different ways to handle a dispute when the client has insufficient available funds.
+//! The default (reject) is tested in tests/errors.rs.
+//!
+//! Tested:
+//!
+//! ✓ allow negative: dispute, resolve
+//! ✓ allow negative: dispute, chargeback
+//! ✓ partial: dispute, resolve
+//! ✓ partial: dispute, chargeback
+//! ✓ partial: held amount survives a snapshot
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+// Client 1 deposits 1.0 and withdraws 0.7 before disputing the deposit.
+//
+const SPENT: &str = "
+
+	      type, client, tx, amount
+	   deposit,      1,  1,    1.0
+	   deposit,      1,  2,    2.0
+	withdrawal,      1,  3,    2.7
+	   dispute,      1,  1,
+
+";
+
+fn bank(policy: DisputePolicy) -> Bank {
+    let mut bank = Bank::new();
+    bank.set_dispute_policy(policy);
+
+    let err = bank.process(CsvParse::try_from(SPENT).unwrap()).errors;
+    assert!(err.is_empty(), "{err:?}");
+
+    bank
+}
+
+fn resolve(bank: &mut Bank, ttype: &str) -> DynResult {
+    let input = format!("type, client, tx, amount\n{ttype}, 1, 1,\n");
+    let err = bank.process(CsvParse::new(input.as_bytes())?).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn allow_negative_resolve() -> DynResult {
+    let mut bank = bank(DisputePolicy::AllowNegative);
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("-0.7"));
+    assert_eq!(client.held(), dec("1.0"));
+    assert_eq!(client.total(), dec("0.3"));
+
+    resolve(&mut bank, "resolve")?;
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("0.3"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("0.3"));
+    assert!(!client.is_locked());
+
+    Ok(())
+}
+
+#[test]
+fn allow_negative_chargeback() -> DynResult {
+    let mut bank = bank(DisputePolicy::AllowNegative);
+
+    resolve(&mut bank, "chargeback")?;
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("-0.7"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("-0.7"));
+    assert!(client.is_locked());
+
+    Ok(())
+}
+
+#[test]
+fn partial_resolve() -> DynResult {
+    let mut bank = bank(DisputePolicy::Partial);
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("0.0"));
+    assert_eq!(client.held(), dec("0.3"));
+    assert_eq!(client.total(), dec("0.3"));
+
+    resolve(&mut bank, "resolve")?;
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("0.3"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("0.3"));
+
+    Ok(())
+}
+
+#[test]
+fn partial_chargeback() -> DynResult {
+    let mut bank = bank(DisputePolicy::Partial);
+
+    resolve(&mut bank, "chargeback")?;
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("0.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("0.0"));
+    assert!(client.is_locked());
+
+    Ok(())
+}
+
+#[test]
+fn partial_snapshot() -> DynResult {
+    let mut data = Vec::new();
+    bank(DisputePolicy::Partial).snapshot(&mut data)?;
+
+    let mut bank = Bank::restore(data.as_slice())?;
+
+    // Deposit more, so a resolve releasing the full amount would be noticed.
+    //
+    let input = "type, client, tx, amount\ndeposit, 1, 4, 5.0\nresolve, 1, 1,\n";
+    let err = bank.process(CsvParse::new(input.as_bytes())?).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("5.3"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("5.3"));
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Tests for disputing withdrawals.
+//!
+//! Tested:
+//!
+//! ✓ disputing a withdrawal is rejected by default
+//! ✓ dispute, resolve a withdrawal
+//! ✓ dispute, chargeback a withdrawal
+//! ✓ dispute a withdrawal with nothing available
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+const WITHDRAWN: &str = "
+
+	      type, client, tx, amount
+	   deposit,      1,  1,    3.0
+	withdrawal,      1,  2,    1.0
+	   dispute,      1,  2,
+
+";
+
+#[test]
+fn disabled() -> DynResult {
+    let mut bank = Bank::new();
+    let err = bank.process(CsvParse::try_from(WITHDRAWN)?).errors;
+
+    assert_eq!(err.len(), 1);
+
+    assert!(matches!(
+        err[0],
+        TransErr::ShouldBeDeposit {
+            trans: Transact {
+                ttype: TransType::Dispute,
+                client: 1,
+                id: 2,
+                ..
+            }
+        }
+    ));
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("2.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("2.0"));
+
+    Ok(())
+}
+
+fn disputed() -> Bank {
+    let mut bank = Bank::new();
+    bank.set_withdrawal_disputes(true);
+
+    let err = bank.process(CsvParse::try_from(WITHDRAWN).unwrap()).errors;
+    assert!(err.is_empty(), "{err:?}");
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("2.0"));
+    assert_eq!(client.held(), dec("1.0"));
+    assert_eq!(client.total(), dec("3.0"));
+
+    bank
+}
+
+// The withdrawal stands.
+//
+#[test]
+fn resolve() -> DynResult {
+    let mut bank = disputed();
+
+    let err = bank
+        .process(CsvParse::new(
+            "type, client, tx, amount\nresolve, 1, 2,".as_bytes(),
+        )?)
+        .errors;
+    assert!(err.is_empty(), "{err:?}");
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("2.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("2.0"));
+    assert!(!client.is_locked());
+
+    assert_eq!(
+        bank.transactions().get(&2).unwrap().state,
+        TransState::Success
+    );
+
+    Ok(())
+}
+
+// The withdrawal is reversed.
+//
+#[test]
+fn chargeback() -> DynResult {
+    let mut bank = disputed();
+
+    let err = bank
+        .process(CsvParse::new(
+            "type, client, tx, amount\nchargeback, 1, 2,".as_bytes(),
+        )?)
+        .errors;
+    assert!(err.is_empty(), "{err:?}");
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("3.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(client.total(), dec("3.0"));
+    assert!(client.is_locked());
+
+    assert_eq!(
+        bank.transactions().get(&2).unwrap().state,
+        TransState::ChargedBack
+    );
+
+    Ok(())
+}
+
+// The dispute policy doesn't come into play, as nothing is taken from available.
+//
+#[test]
+fn nothing_available() -> DynResult {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		withdrawal,      1,  2,    1.0
+		   dispute,      1,  2,
+
+	";
+
+    let mut bank = Bank::new();
+    bank.set_withdrawal_disputes(true);
+
+    let err = bank.process(CsvParse::try_from(input)?).errors;
+    assert!(err.is_empty(), "{err:?}");
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("0.0"));
+    assert_eq!(client.held(), dec("1.0"));
+    assert_eq!(client.total(), dec("1.0"));
+
+    Ok(())
+}
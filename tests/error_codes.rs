@@ -0,0 +1,148 @@
+//! Tests for the stable error codes and categories.
+//!
+//! Tested:
+//!
+//! ✓ codes are unique and well formed
+//! ✓ code and category of errors from parsing and from the bank
+//! ✓ the code is part of the message
+//! ✓ single line format with code and category
+//! ✓ run binary: errors on stderr carry their code
+//! ✓ run binary: a fatal error is a single line on stderr
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{collections::HashSet, process::Command},
+};
+
+#[test]
+fn unique() {
+    let codes: HashSet<_> = TransErrKind::ALL.iter().map(|k| k.code()).collect();
+
+    assert_eq!(codes.len(), TransErrKind::ALL.len());
+
+    for code in codes {
+        assert!(code.starts_with("E_"), "{code}");
+        assert!(
+            code.bytes().all(|b| b.is_ascii_uppercase() || b == b'_'),
+            "{code}"
+        );
+    }
+}
+
+#[test]
+fn codes() -> DynResult {
+    let input = "type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 5.0
+deposit, 1, x, 1.0
+";
+
+    let mut bank = Bank::new();
+    let errs = bank.process(CsvParse::new(input.as_bytes())?).errors;
+
+    let codes: Vec<_> = errs.iter().map(|e| (e.code(), e.category())).collect();
+
+    assert_eq!(
+        codes,
+        vec![
+            ("E_DUPLICATE_TX", ErrorCategory::BusinessRule),
+            ("E_INSUFFICIENT_FUNDS", ErrorCategory::BusinessRule),
+            ("E_INVALID_ROW", ErrorCategory::Input),
+        ]
+    );
+
+    assert_eq!(TransErr::NoHeader.code(), "E_NO_HEADER");
+    assert_eq!(TransErr::NoHeader.category(), ErrorCategory::Input);
+    assert_eq!(TransErrKind::Snapshot.category(), ErrorCategory::Io);
+    assert_eq!(ErrorCategory::BusinessRule.to_string(), "business-rule");
+
+    Ok(())
+}
+
+#[test]
+fn message() {
+    assert!(TransErr::NoHeader
+        .to_string()
+        .starts_with("\nError [E_NO_HEADER]: "));
+}
+
+#[test]
+fn single_line() -> DynResult {
+    let input = "type, client, tx, amount\nwithdrawal,1,\"2\r\n\",5.0\n";
+
+    let mut bank = Bank::new();
+    let errs = bank.process(CsvParse::new(input.as_bytes())?).errors;
+
+    assert_eq!(
+        TransErr::NoHeader.single_line().to_string(),
+        "E_NO_HEADER input: Only CSV files with a valid header are supported. For a valid header \
+         the first line should be: \"type, client, tx, amount\""
+    );
+
+    let line = errs[0].single_line().to_string();
+
+    assert!(!line.contains('\n'), "{line}");
+    assert!(
+        line.starts_with("E_NO_CLIENT business-rule: Cannot withdraw"),
+        "{line}"
+    );
+    assert!(
+        line.ends_with(" At line 2 (byte 25): \"withdrawal,1,\\\"2\\r\\n\\\",5.0\""),
+        "{line}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cli() -> DynResult {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("tests/data/invalid_line.csv")
+        .output()?;
+
+    let err = std::str::from_utf8(&output.stderr)?;
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        err.lines().filter(|l| l.starts_with("E_")).count(),
+        2,
+        "{err}"
+    );
+    assert!(err.contains("\nE_INVALID_ROW input: "), "{err}");
+    assert!(
+        err.contains("E_INSUFFICIENT_FUNDS business-rule: "),
+        "{err}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cli_fatal() -> DynResult {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("tests/data/does_not_exist.csv")
+        .output()?;
+
+    let err = std::str::from_utf8(&output.stderr)?;
+
+    // Cargo writes to stderr as well, the error is what comes after it.
+    //
+    let lines: Vec<_> = err.lines().skip_while(|l| !l.starts_with("E_")).collect();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(lines.len(), 1, "{err}");
+    assert!(lines[0].starts_with("E_INPUT_FILE "), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Tests for streaming errors to an ErrorSink with Bank::process_into.
+//!
+//! Tested:
+//!
+//! ✓ collect errors in a Vec
+//! ✓ write errors to a writer as they happen
+//! ✓ only count errors
+//! ✓ retained errors go to the bank instead of the sink
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+const BATCH: &str = "
+
+	      type, client, tx, amount
+	   deposit,      1,  1,    1.0
+	withdrawal,      1,  2,    5.0
+	   dispute,      2,  1,
+	   deposit,      1,  3,    1.0
+
+";
+
+#[test]
+fn vec() -> DynResult {
+    let mut bank = Bank::new();
+    let mut errors = Vec::new();
+
+    let report = bank.process_into(CsvParse::try_from(BATCH)?, &mut errors);
+
+    assert_eq!(report.failed, 2);
+    assert!(report.errors.is_empty());
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], TransErr::InsufficientFunds { .. }));
+    assert!(matches!(errors[1], TransErr::NoClient { .. }));
+
+    Ok(())
+}
+
+#[test]
+fn writer() -> DynResult {
+    let mut bank = Bank::new();
+    let mut sink = WriteSink::new(Vec::new());
+
+    let report = bank.process_into(CsvParse::try_from(BATCH)?, &mut sink);
+
+    assert_eq!(report.succeeded, 2);
+    assert_eq!(sink.count(), 2);
+
+    let out = String::from_utf8(sink.finish()?)?;
+
+    assert_eq!(out.matches("\nError [").count(), 2);
+    assert!(out.contains("Error [E_INSUFFICIENT_FUNDS]: "), "{out}");
+    assert!(out.contains("insufficient funds"), "{out}");
+
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("2.0"));
+
+    Ok(())
+}
+
+#[test]
+fn count() -> DynResult {
+    let mut bank = Bank::new();
+    let mut sink = CountSink::new();
+
+    bank.process_into(CsvParse::try_from(BATCH)?, &mut sink);
+
+    assert_eq!(sink.count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn retained() -> DynResult {
+    let mut bank = Bank::new();
+    let mut sink = CountSink::new();
+
+    bank.set_retain_errors(true);
+    bank.process_into(CsvParse::try_from(BATCH)?, &mut sink);
+
+    assert_eq!(sink.count(), 0);
+    assert_eq!(bank.errors().len(), 2);
+
+    Ok(())
+}
@@ -26,7 +26,13 @@
 //! - DeserializeTransact:
 //!
 //!   ✓ Create a transaction with a negative amount
+//!
+//! - Overflow:
+//!
+//!   ✓ deposit beyond the largest representable balance
 //
+#![cfg(feature = "csv")]
+
 mod common;
 
 use {common::*, libtransact::*, pretty_assertions::assert_eq};
@@ -40,7 +46,7 @@ fn locked_client() -> Bank {
         Ok(Transact::new(TransType::Dispute, 1, 1)),
     ];
 
-    let errs = bank.process(trs.into_iter());
+    let errs = bank.process(trs.into_iter()).errors;
 
     assert!(errs.is_empty());
 
@@ -66,7 +72,7 @@ fn locked_deposit() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = locked_client();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -78,6 +84,7 @@ fn locked_deposit() -> DynResult {
             state : TransState::New       ,
             client: 1                     ,
             id    : 3                     ,
+            ..
 
         }} if a == &dec("1.0")
     ));
@@ -105,7 +112,7 @@ fn locked_withdraw() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = locked_client();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -117,6 +124,7 @@ fn locked_withdraw() -> DynResult {
             state : TransState::New        ,
             client: 1                      ,
             id    : 3                      ,
+            ..
 
         }} if a == &dec("1.0")
     ));
@@ -144,7 +152,7 @@ fn locked_dispute() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = locked_client();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -156,6 +164,7 @@ fn locked_dispute() -> DynResult {
                 state: TransState::New,
                 client: 1,
                 id: 2,
+                ..
             }
         }
     ));
@@ -183,7 +192,7 @@ fn locked_resolve() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = locked_client();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -195,6 +204,7 @@ fn locked_resolve() -> DynResult {
                 state: TransState::New,
                 client: 1,
                 id: 1,
+                ..
             }
         }
     ));
@@ -222,7 +232,7 @@ fn locked_charge_back() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = locked_client();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -234,6 +244,7 @@ fn locked_charge_back() -> DynResult {
                 state: TransState::New,
                 client: 1,
                 id: 1,
+                ..
             }
         }
     ));
@@ -266,7 +277,7 @@ fn dup_deposit_deposit() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -278,6 +289,7 @@ fn dup_deposit_deposit() -> DynResult {
             state : TransState::New       ,
             client: 1                     ,
             id    : 1                     ,
+            ..
 
         }} if a == &dec("1.5")
     ));
@@ -307,7 +319,7 @@ fn dup_deposit_withdraw_deposit() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -319,6 +331,7 @@ fn dup_deposit_withdraw_deposit() -> DynResult {
             state : TransState::New       ,
             client: 1                     ,
             id    : 2                     ,
+            ..
 
         }} if a == &dec("2.0")
     ));
@@ -347,7 +360,7 @@ fn dup_deposit_withdraw() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -359,6 +372,7 @@ fn dup_deposit_withdraw() -> DynResult {
             state : TransState::New        ,
             client: 1                      ,
             id    : 1                      ,
+            ..
 
         }} if a == &dec("0.5")
     ));
@@ -391,7 +405,7 @@ fn withdraw_too_much() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -403,6 +417,7 @@ fn withdraw_too_much() -> DynResult {
             state : TransState::New        ,
             client: 1                      ,
             id    : 2                      ,
+            ..
 
         }} if a == &dec("1.5")
     ));
@@ -432,7 +447,7 @@ fn dispute_after_withdraw() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -444,6 +459,7 @@ fn dispute_after_withdraw() -> DynResult {
                 state: TransState::New,
                 client: 1,
                 id: 1,
+                ..
             }
         }
     ));
@@ -471,7 +487,7 @@ fn negative_amount() -> DynResult {
     let parser = CsvParse::try_from(input)?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1);
 
@@ -483,3 +499,39 @@ fn negative_amount() -> DynResult {
 
     Ok(())
 }
+
+// deposit beyond the largest representable balance
+//
+#[test]
+fn deposit_overflow() -> DynResult {
+    let input = format!(
+        "type, client, tx, amount\ndeposit, 1, 1, {}\ndeposit, 1, 2, 1.0\n",
+        Amount::MAX
+    );
+
+    let parser = CsvParse::new(input.as_bytes())?;
+    let mut bank = Bank::new();
+
+    let err = bank.process(parser).errors;
+
+    assert_eq!(err.len(), 1);
+
+    assert!(matches!(
+        err[0],
+        TransErr::Overflow {
+            trans: Transact {
+                state: TransState::New,
+                client: 1,
+                id: 2,
+                ..
+            }
+        }
+    ));
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), Amount::MAX);
+    assert_eq!(client.total(), Amount::MAX);
+
+    Ok(())
+}
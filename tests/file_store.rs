@@ -0,0 +1,311 @@
+//! Tests for the append-only log backed transaction store.
+//!
+//! Tested:
+//!
+//! ✓ transactions and state changes survive reopening the store
+//! ✓ a torn trailing record is truncated on reopen
+//! ✓ a corrupt record in the middle of the log is reported
+//! ✓ dispute a deposit processed by an earlier bank on the same log
+//! ✓ a file that is not a transaction log is rejected
+//! ✓ resolve a partial dispute opened by an earlier bank on the same log
+//! ✓ a version 1 log is upgraded before the first dispute is appended
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{collections::HashMap, fs::OpenOptions, io::Write},
+};
+
+#[test]
+fn reopen() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    {
+        let mut store = FileStore::open(&path)?;
+
+        let mut trans = Transact::new(TransType::Deposit(dec("1.5")), 1, 1);
+        trans.state = TransState::Success;
+
+        store.insert(trans)?;
+        store.set_state(1, TransState::Disputed)?;
+    }
+
+    let store = FileStore::open(&path)?;
+
+    assert_eq!(store.len(), 1);
+    assert!(store.contains(1)?);
+    assert!(!store.contains(2)?);
+
+    let trans = store.get(1)?.unwrap().into_owned();
+
+    assert_eq!(trans.ttype, TransType::Deposit(dec("1.5")));
+    assert_eq!(trans.state, TransState::Disputed);
+    assert_eq!(trans.client, 1);
+
+    Ok(())
+}
+
+// Simulate a process being killed in the middle of writing a record.
+//
+#[test]
+fn torn_write() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    {
+        let mut store = FileStore::open(&path)?;
+        store.insert(Transact::new(TransType::Deposit(dec("1.5")), 1, 1))?;
+        store.insert(Transact::new(TransType::Deposit(dec("2.5")), 1, 2))?;
+    }
+
+    let intact = std::fs::metadata(&path)?.len();
+
+    // Cut the last record in half.
+    //
+    let file = OpenOptions::new().write(true).open(&path)?;
+    file.set_len(intact - 5)?;
+    drop(file);
+
+    let mut store = FileStore::open(&path)?;
+
+    assert_eq!(store.len(), 1);
+    assert!(store.contains(1)?);
+    assert!(!store.contains(2)?);
+
+    // Appending after the truncation works.
+    //
+    store.insert(Transact::new(TransType::Deposit(dec("3.5")), 1, 3))?;
+    drop(store);
+
+    let store = FileStore::open(&path)?;
+
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.get(3)?.unwrap().ttype, TransType::Deposit(dec("3.5")));
+
+    Ok(())
+}
+
+// Garbage after the last record is treated as a torn write.
+//
+#[test]
+fn torn_length() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    {
+        let mut store = FileStore::open(&path)?;
+        store.insert(Transact::new(TransType::Deposit(dec("1.5")), 1, 1))?;
+    }
+
+    let intact = std::fs::metadata(&path)?.len();
+
+    OpenOptions::new()
+        .append(true)
+        .open(&path)?
+        .write_all(&[0xff, 0xff])?;
+
+    let store = FileStore::open(&path)?;
+
+    assert_eq!(store.len(), 1);
+    assert_eq!(std::fs::metadata(&path)?.len(), intact);
+
+    Ok(())
+}
+
+// A bad checksum that isn't on the last record is corruption, not a torn write.
+//
+#[test]
+fn corrupt_middle() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    {
+        let mut store = FileStore::open(&path)?;
+        store.insert(Transact::new(TransType::Deposit(dec("1.5")), 1, 1))?;
+        store.insert(Transact::new(TransType::Deposit(dec("2.5")), 1, 2))?;
+    }
+
+    // Past the 12 byte header, in the first record.
+    //
+    let mut data = std::fs::read(&path)?;
+    data[18] ^= 0xff;
+    std::fs::write(&path, data)?;
+
+    assert!(matches!(
+        FileStore::open(&path),
+        Err(TransErr::Store { .. })
+    ));
+
+    Ok(())
+}
+
+// Deposits from a previous run can be disputed.
+//
+#[test]
+fn bank_across_runs() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    let day1 = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		   deposit,      1,  2,    2.0
+
+	";
+
+    let mut bank = Bank::with_stores(FileStore::open(&path)?, HashMap::new());
+    assert!(bank.process(CsvParse::try_from(day1)?).errors.is_empty());
+
+    let clients = bank.clients().clone();
+    drop(bank);
+
+    let day2 = "
+
+		      type, client, tx, amount
+		   dispute,      1,  1,
+		   deposit,      1,  2,    2.0
+
+	";
+
+    let mut bank = Bank::with_stores(FileStore::open(&path)?, clients);
+    let err = bank.process(CsvParse::try_from(day2)?).errors;
+
+    assert_eq!(err.len(), 1);
+    assert!(matches!(err[0], TransErr::DuplicateTransact { .. }));
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("2.0"));
+    assert_eq!(client.held(), dec("1.0"));
+    assert_eq!(client.total(), dec("3.0"));
+
+    assert_eq!(
+        bank.transactions().get(1)?.unwrap().state,
+        TransState::Disputed
+    );
+
+    Ok(())
+}
+
+// Other files are not mistaken for a log, and a log with a newer version is not misread.
+//
+#[test]
+fn header() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    std::fs::write(&path, "type, client, tx, amount\n")?;
+
+    assert!(matches!(
+        FileStore::open(&path),
+        Err(TransErr::Store { .. })
+    ));
+
+    std::fs::remove_file(&path)?;
+    drop(FileStore::open(&path)?);
+
+    let mut data = std::fs::read(&path)?;
+    assert_eq!(&data[..8], b"TRANSLOG");
+
+    data[8] = 99;
+    std::fs::write(&path, data)?;
+
+    assert!(matches!(
+        FileStore::open(&path),
+        Err(TransErr::Store { .. })
+    ));
+
+    Ok(())
+}
+
+// The amount held by a partial dispute is kept in the log, so it is released in full later.
+//
+#[test]
+fn partial_dispute_across_runs() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    let day1 = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    5.0
+		withdrawal,      1,  2,    3.0
+		   dispute,      1,  1,
+
+	";
+
+    let mut bank = Bank::with_stores(FileStore::open(&path)?, HashMap::new());
+    bank.set_dispute_policy(DisputePolicy::Partial);
+
+    assert!(bank.process(CsvParse::try_from(day1)?).errors.is_empty());
+
+    let clients = bank.clients().clone();
+    drop(bank);
+
+    assert_eq!(FileStore::open(&path)?.held(1)?, Some(dec("2.0")));
+
+    let day2 = "
+
+		      type, client, tx, amount
+		   resolve,      1,  1,
+
+	";
+
+    let mut bank = Bank::with_stores(FileStore::open(&path)?, clients);
+    bank.set_dispute_policy(DisputePolicy::Partial);
+
+    assert!(bank.process(CsvParse::try_from(day2)?).errors.is_empty());
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("2.0"));
+    assert_eq!(client.held(), dec("0.0"));
+    assert_eq!(bank.transactions().held(1)?, None);
+
+    Ok(())
+}
+
+// Version 1 logs have no dispute records, appending one upgrades the header.
+//
+#[test]
+fn upgrade_v1() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    {
+        let mut store = FileStore::open(&path)?;
+
+        let mut trans = Transact::new(TransType::Deposit(dec("1.5")), 1, 1);
+        trans.state = TransState::Success;
+
+        store.insert(trans)?;
+    }
+
+    let mut data = std::fs::read(&path)?;
+    data[8..12].copy_from_slice(&1u32.to_le_bytes());
+    std::fs::write(&path, &data)?;
+
+    // Reading doesn't change the header.
+    //
+    drop(FileStore::open(&path)?);
+    assert_eq!(&std::fs::read(&path)?[8..12], &1u32.to_le_bytes());
+
+    FileStore::open(&path)?.set_disputed(1, dec("1.0"))?;
+
+    let data = std::fs::read(&path)?;
+    assert_eq!(&data[8..12], &2u32.to_le_bytes());
+
+    let store = FileStore::open(&path)?;
+
+    assert_eq!(store.held(1)?, Some(dec("1.0")));
+    assert_eq!(store.len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Replay the inputs in fuzz/regressions through the fuzz targets. When the fuzzer finds a
+//! crash, copy the artifact into the directory of the target so it stays fixed.
+//!
+//! Tested:
+//!
+//! ✓ csv_parse
+//! ✓ transact_record
+//! ✓ bank_invariants
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+#[path = "../fuzz/src/lib.rs"]
+mod fuzz;
+
+use {common::*, std::fs};
+
+fn replay(target: &str, run: fn(&[u8])) -> DynResult {
+    let mut count = 0;
+
+    for entry in fs::read_dir(format!("fuzz/regressions/{target}"))? {
+        let path = entry?.path();
+        let data = fs::read(&path)?;
+
+        println!("{}", path.display());
+        run(&data);
+
+        count += 1;
+    }
+
+    assert!(count > 0, "no inputs for {target}");
+
+    Ok(())
+}
+
+#[test]
+fn csv_parse() -> DynResult {
+    replay("csv_parse", fuzz::csv_parse)
+}
+
+#[test]
+fn transact_record() -> DynResult {
+    replay("transact_record", fuzz::transact_record)
+}
+
+#[test]
+fn bank_invariants() -> DynResult {
+    replay("bank_invariants", fuzz::bank_invariants)
+}
@@ -0,0 +1,175 @@
+//! Tests for exporting the client data to JSON.
+//!
+//! Tested:
+//!
+//! ✓ array and newline delimited styles
+//! ✓ amounts are strings, with the export options applied
+//! ✓ no clients
+//! ✓ errors writing the output are returned as SerializeClients
+//! ✓ serializing a Client, with and without unlock reason
+//! ✓ the cli --format flag
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    serde_json::{json, Value},
+    std::{collections::HashMap, io, process::Command},
+};
+
+fn bank() -> DynResult<Bank> {
+    let input = "
+		type,       client, tx, amount
+		deposit,         2,  1,    5.0
+		deposit,         1,  2, 2.1255
+		dispute,         2,  1,
+	";
+
+    let mut bank = Bank::new();
+    let errs = bank.process(CsvParse::try_from(input)?).errors;
+
+    assert!(errs.is_empty(), "{errs:?}");
+
+    Ok(bank)
+}
+
+#[test]
+fn styles() -> DynResult {
+    let bank = bank()?;
+
+    let array = JsonExport::export(bank.clients(), JsonStyle::Array)?;
+
+    assert_eq!(
+        array,
+        "[\n\
+         {\"client\":1,\"available\":\"2.1255\",\"held\":\"0\",\"total\":\"2.1255\",\"locked\":false},\n\
+         {\"client\":2,\"available\":\"0\",\"held\":\"5\",\"total\":\"5\",\"locked\":false}\n\
+         ]\n"
+    );
+
+    let lines = JsonExport::export(bank.clients(), JsonStyle::Lines)?;
+
+    let objects: Vec<Value> = lines
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(
+        Value::Array(objects),
+        serde_json::from_str::<Value>(&array)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn options() -> DynResult {
+    let bank = bank()?;
+
+    let mut options = ExportOptions::new();
+    options
+        .sort_by(SortBy::Total)
+        .descending(true)
+        .columns([ClientColumn::Total, ClientColumn::Client])
+        .decimals(Some(2));
+
+    let out = JsonExport::export_with(bank.clients(), &options, JsonStyle::Array)?;
+
+    assert_eq!(
+        serde_json::from_str::<Value>(&out)?,
+        json!([
+            { "total": "5.00", "client": 2 },
+            { "total": "2.13", "client": 1 },
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn empty() -> DynResult {
+    let clients = HashMap::new();
+
+    assert_eq!(JsonExport::export(&clients, JsonStyle::Array)?, "[\n]\n");
+    assert_eq!(JsonExport::export(&clients, JsonStyle::Lines)?, "");
+
+    Ok(())
+}
+
+#[test]
+fn write_error() -> DynResult {
+    struct Closed;
+
+    impl io::Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let bank = bank()?;
+
+    for style in [JsonStyle::Array, JsonStyle::Lines] {
+        let err = JsonExport::write_to(bank.clients(), style, Closed).unwrap_err();
+
+        assert_eq!(err.kind(), TransErrKind::SerializeClients);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn serialize_client() -> DynResult {
+    let mut client = Client::new(7);
+
+    assert_eq!(
+        serde_json::to_value(&client)?,
+        json!({ "client": 7, "available": "0", "held": "0", "total": "0", "locked": false })
+    );
+
+    client.lock().unlock("verified by phone");
+
+    assert_eq!(
+        serde_json::to_value(&client)?["unlock_reason"],
+        json!("verified by phone")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cli() -> DynResult {
+    let run = |format: &str| {
+        Command::new("cargo")
+            .args(["run", "--", "--format", format, "tests/data/simple.csv"])
+            .output()
+    };
+
+    let json = run("json")?;
+    let clients: Value = serde_json::from_slice(&json.stdout)?;
+
+    assert_eq!(json.status.code(), Some(0));
+    assert_eq!(
+        clients[1],
+        json!({ "client": 2, "available": "1.9", "held": "0", "total": "1.9", "locked": false })
+    );
+
+    let ndjson = run("ndjson")?;
+
+    assert_eq!(ndjson.status.code(), Some(0));
+    assert_eq!(std::str::from_utf8(&ndjson.stdout)?.lines().count(), 2);
+
+    let output = run("xml")?;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(std::str::from_utf8(&output.stderr)?.contains("--format requires csv, json or ndjson"));
+
+    Ok(())
+}
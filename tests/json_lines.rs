@@ -0,0 +1,280 @@
+//! Tests for JSON Lines input.
+//!
+//! Tested:
+//!
+//! ✓ file input gives the same result as the csv version
+//! ✓ amounts as strings, numbers, null and missing
+//! ✓ numbers are not rounded through a float
+//! ✓ invalid transactions are validated like csv
+//! ✓ invalid lines are reported with their position and skipped
+//! ✓ blank lines and byte order mark are ignored, \r\n line endings
+//! ✓ the type is trimmed like a csv field
+//! ✓ a line longer than the maximum ends the input
+//! ✓ the cli picks the format by extension or flag
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{path::Path, process::Command},
+};
+
+// The transactions, or the kind and line of errors.
+//
+fn summary(input: &[u8]) -> Vec<Result<TransType, (TransErrKind, u64)>> {
+    JsonLinesParse::new(input)
+        .map(|r| match r {
+            Ok(t) => Ok(t.ttype),
+            Err(e) => Err((e.kind(), e.position().map_or(0, |p| p.line))),
+        })
+        .collect()
+}
+
+#[test]
+fn file_input() -> DynResult {
+    let mut json = Bank::new();
+    let mut csv = Bank::new();
+
+    let errs = json
+        .process(JsonLinesParse::try_from(Path::new(
+            "tests/data/simple.jsonl",
+        ))?)
+        .errors;
+
+    assert!(errs.is_empty(), "{errs:?}");
+
+    csv.process(CsvParse::try_from(Path::new("tests/data/simple.csv"))?);
+
+    assert_eq!(json.clients(), csv.clients());
+    assert_eq!(json.clients().get(&1).unwrap().total(), dec("1.5"));
+    assert_eq!(json.clients().get(&2).unwrap().total(), dec("1.9"));
+
+    Ok(())
+}
+
+#[test]
+fn amounts() {
+    let input = r#"
+		{"type": "deposit", "client": 1, "tx": 1, "amount": "1.5"}
+		{"type": "deposit", "client": 1, "tx": 2, "amount": 2}
+		{"type": "deposit", "client": 1, "tx": 3, "amount": " 0.25 "}
+		{"type": "dispute", "client": 1, "tx": 1, "amount": null}
+		{"type": "resolve", "client": 1, "tx": 1}
+		{"type": "dispute", "client": 1, "tx": 2, "amount": ""}
+	"#;
+
+    assert_eq!(
+        summary(input.as_bytes()),
+        vec![
+            Ok(TransType::Deposit(dec("1.5"))),
+            Ok(TransType::Deposit(dec("2"))),
+            Ok(TransType::Deposit(dec("0.25"))),
+            Ok(TransType::Dispute),
+            Ok(TransType::Resolve),
+            Ok(TransType::Dispute),
+        ]
+    );
+
+    // Through an f64 these would not be exact.
+    //
+    let input = r#"
+		{"type": "deposit", "client": 1, "tx": 1, "amount": 0.1}
+		{"type": "deposit", "client": 1, "tx": 2, "amount": 12345678901234.5678}
+		{"type": "deposit", "client": 1, "tx": 3, "amount": 0.00001}
+	"#;
+
+    let invalid = |line| Err((TransErrKind::DeserializeTransact, line));
+
+    assert_eq!(
+        summary(input.as_bytes()),
+        vec![
+            Ok(TransType::Deposit(dec("0.1"))),
+            Ok(TransType::Deposit(dec("12345678901234.5678"))),
+            invalid(4),
+        ]
+    );
+}
+
+#[test]
+fn invalid_transactions() {
+    let input = r#"
+		{"type": "deposit",    "client": 1, "tx": 1, "amount": "-1"}
+		{"type": "deposit",    "client": 1, "tx": 2, "amount": 0}
+		{"type": "payment",    "client": 1, "tx": 3, "amount": "1"}
+		{"type": "withdrawal", "client": 1, "tx": 4}
+		{"type": "chargeback", "client": 1, "tx": 5, "amount": "1"}
+		{"type": "deposit",    "client": 1, "tx": 6, "amount": "1.00001"}
+		{"type": "deposit",    "client": 1, "tx": 7, "amount": true}
+	"#;
+
+    let kinds: Vec<_> = JsonLinesParse::new(input.as_bytes())
+        .map(|r| match r.unwrap_err() {
+            TransErr::DeserializeTransact { kind, .. } => kind,
+            e => panic!("unexpected error: {e}"),
+        })
+        .collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            DeserTransactKind::AmountNegative,
+            DeserTransactKind::AmountZero,
+            DeserTransactKind::UnknownTransType,
+            DeserTransactKind::MissingAmount,
+            DeserTransactKind::UnexpectedAmount,
+            DeserTransactKind::TooManyDecimals,
+            DeserTransactKind::InvalidAmount,
+        ]
+    );
+}
+
+#[test]
+fn invalid_lines() {
+    let input = b"{\"type\": \"deposit\", \"client\": 1, \"tx\": 1, \"amount\": \"1\"}\n\
+        type,client,tx,amount\n\
+        {\"type\": \"deposit\", \"client\": -1, \"tx\": 2, \"amount\": \"1\"}\n\
+        {\"type\": \"deposit\", \"tx\": 3, \"amount\": \"1\"}\n\
+        [\"deposit\", 1, 4, \"1\"]\n\
+        {\"type\": \"deposit\", \"client\": 1, \"tx\": 5, \"amount\": \"\xFF\"}\n\
+        {\"type\": \"deposit\", \"client\": 1, \"tx\": 6, \"amount\": \"1\"}\n";
+
+    let invalid = |line| Err((TransErrKind::DeserializeCsv, line));
+
+    assert_eq!(
+        summary(input),
+        vec![
+            Ok(TransType::Deposit(dec("1"))),
+            invalid(2),
+            invalid(3),
+            invalid(4),
+            invalid(5),
+            invalid(6),
+            Ok(TransType::Deposit(dec("1"))),
+        ]
+    );
+
+    // The position has the line as written.
+    //
+    let err = JsonLinesParse::new(&input[..]).nth(1).unwrap().unwrap_err();
+
+    assert_eq!(
+        err.position(),
+        Some(&Position {
+            line: 2,
+            byte: 57,
+            row: b"type,client,tx,amount".to_vec()
+        })
+    );
+}
+
+#[test]
+fn blank_lines() {
+    let input = "\u{feff}\r\n\
+        {\"type\": \"deposit\", \"client\": 1, \"tx\": 1, \"amount\": \"1\"}\r\n\
+        \r\n   \r\n\
+        {\"type\": \"dispute\", \"client\": 1, \"tx\": 1}";
+
+    let trans: Vec<_> = JsonLinesParse::new(input.as_bytes())
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(trans.len(), 2);
+    assert_eq!(
+        trans[0].pos.as_ref().map(|p| (p.line, p.byte)),
+        Some((2, 5))
+    );
+    assert_eq!(
+        trans[1].pos.as_ref().map(|p| (p.line, p.byte)),
+        Some((5, 70))
+    );
+    assert_eq!(trans[1].ttype, TransType::Dispute);
+}
+
+#[test]
+fn trimmed_type() {
+    let input = r#"
+		{"type": " deposit ", "client": 1, "tx": 1, "amount": "1.5"}
+		{"type": "\tdispute", "client": 1, "tx": 1}
+	"#;
+
+    assert_eq!(
+        summary(input.as_bytes()),
+        vec![Ok(TransType::Deposit(dec("1.5"))), Ok(TransType::Dispute)]
+    );
+}
+
+#[test]
+fn max_line_len() {
+    let line = r#"{"type": "deposit", "client": 1, "tx": 1, "amount": "1"}"#;
+    let long = format!(
+        r#"{{"type": "deposit", "client": 1, "tx": 2, "amount": "1.{}"}}"#,
+        "0".repeat(100)
+    );
+    let input = format!("\u{feff}{line}\r\n\n{long}\n{line}\n");
+
+    let mut parser = JsonLinesParse::new(input.as_bytes());
+    parser.set_max_line_len(Some(line.len()));
+
+    let results: Vec<_> = parser.collect();
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+
+    match &results[1] {
+        Err(e @ TransErr::DeserializeCsv { source, .. }) => {
+            assert_eq!(
+                e.position().map(|p| (p.line, p.byte)),
+                Some((3, 3 + line.len() as u64 + 3))
+            );
+            assert!(source.to_string().contains("maximum"), "{source}");
+        }
+
+        other => panic!("unexpected: {other:?}"),
+    }
+}
+
+#[test]
+fn cli() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let ndjson = dir.path().join("day1.ndjson");
+    let txt = dir.path().join("day1.txt");
+
+    std::fs::copy("tests/data/simple.jsonl", &ndjson)?;
+    std::fs::copy("tests/data/simple.jsonl", &txt)?;
+
+    let run = |args: &[&std::ffi::OsStr]| {
+        Command::new("cargo")
+            .arg("run")
+            .arg("--")
+            .args(args)
+            .output()
+    };
+
+    for args in [
+        vec!["tests/data/simple.jsonl".as_ref()],
+        vec![ndjson.as_os_str()],
+        vec!["--input-format".as_ref(), "jsonl".as_ref(), txt.as_os_str()],
+    ] {
+        let output = run(&args)?;
+        let out = std::str::from_utf8(&output.stdout)?;
+
+        assert_eq!(output.status.code(), Some(0), "{args:?}");
+        assert!(out.contains("1,        1.5,          0,        1.5,      false"));
+        assert!(out.contains("2,        1.9,          0,        1.9,      false"));
+    }
+
+    // Without the flag a .txt file is csv, which has no header here.
+    //
+    assert_eq!(run(&[txt.as_os_str()])?.status.code(), Some(1));
+
+    let output = run(&["--input-format".as_ref(), "xml".as_ref(), txt.as_os_str()])?;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(std::str::from_utf8(&output.stderr)?.contains("--input-format requires csv or jsonl"));
+
+    Ok(())
+}
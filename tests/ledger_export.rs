@@ -0,0 +1,154 @@
+//! Tests for exporting the stored transactions with their final state.
+//!
+//! Tested:
+//!
+//! ✓ csv with every state, ordered by id
+//! ✓ json array and newline delimited
+//! ✓ unlocks are part of the ledger, without amount and with their reason, quoted if needed
+//! ✓ other transaction stores
+//! ✓ the cli --ledger flag
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    serde_json::{json, Value},
+    std::process::Command,
+};
+
+fn bank() -> DynResult<Bank> {
+    let input = "
+		type,       client, tx, amount
+		deposit,         1,  4,    5.0
+		deposit,         1,  2,    1.5
+		withdrawal,      1,  3,   0.25
+		deposit,         2,  1,      3
+		dispute,         1,  4,
+		dispute,         2,  1,
+		chargeback,      2,  1,
+	";
+
+    let mut bank = Bank::new();
+    let errs = bank.process(CsvParse::try_from(input)?).errors;
+
+    assert!(errs.is_empty(), "{errs:?}");
+
+    Ok(bank)
+}
+
+fn csv(store: &(impl TransactionStore + ?Sized)) -> DynResult<String> {
+    let mut out = Vec::new();
+    LedgerExport::write_csv(store, &mut out)?;
+
+    Ok(String::from_utf8(out)?)
+}
+
+#[test]
+fn states() -> DynResult {
+    let mut bank = bank()?;
+
+    assert_eq!(
+        csv(bank.transactions())?,
+        "type,client,tx,amount,reason,state\n\
+         deposit,2,1,3,,charged-back\n\
+         deposit,1,2,1.5,,success\n\
+         withdrawal,1,3,0.25,,success\n\
+         deposit,1,4,5,,disputed\n"
+    );
+
+    bank.unlock(2, 5, "refunded, \"by mistake\"")?;
+
+    assert!(csv(bank.transactions())?
+        .ends_with("unlock,2,5,,\"refunded, \"\"by mistake\"\"\",success\n"));
+
+    Ok(())
+}
+
+#[test]
+fn json() -> DynResult {
+    let bank = bank()?;
+
+    let mut array = Vec::new();
+    LedgerExport::write_json(bank.transactions(), JsonStyle::Array, &mut array)?;
+
+    let array: Value = serde_json::from_slice(&array)?;
+
+    assert_eq!(
+        array[0],
+        json!({
+            "type": "deposit",
+            "client": 2,
+            "tx": 1,
+            "amount": "3",
+            "reason": null,
+            "state": "charged-back"
+        })
+    );
+    assert_eq!(array[3]["state"], "disputed");
+
+    let mut lines = Vec::new();
+    LedgerExport::write_json(bank.transactions(), JsonStyle::Lines, &mut lines)?;
+
+    let lines: Vec<Value> = std::str::from_utf8(&lines)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(Value::Array(lines), array);
+
+    Ok(())
+}
+
+#[test]
+fn file_store() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let mut store = FileStore::open(dir.path().join("transactions.log"))?;
+
+    store.insert(Transact::new(TransType::Deposit(dec("1.5")), 1, 2))?;
+    store.insert(Transact::new(TransType::WithDraw(dec("0.5")), 1, 1))?;
+    store.set_state(2, TransState::Disputed)?;
+
+    assert_eq!(
+        csv(&store)?,
+        "type,client,tx,amount,reason,state\n\
+         withdrawal,1,1,0.5,,new\n\
+         deposit,1,2,1.5,,disputed\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cli() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let ledger = dir.path().join("ledger.csv");
+    let ndjson = dir.path().join("ledger.ndjson");
+
+    for path in [&ledger, &ndjson] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "--ledger"])
+            .arg(path)
+            .arg("tests/data/simple.csv")
+            .output()?;
+
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    assert_eq!(
+        std::fs::read_to_string(&ledger)?,
+        "type,client,tx,amount,reason,state\n\
+         deposit,1,1,1,,success\n\
+         deposit,2,2,2,,success\n\
+         deposit,1,3,2,,success\n\
+         withdrawal,1,4,1.5,,success\n\
+         withdrawal,2,5,0.1,,success\n"
+    );
+
+    assert_eq!(std::fs::read_to_string(&ndjson)?.lines().count(), 5);
+
+    Ok(())
+}
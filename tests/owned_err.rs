@@ -0,0 +1,101 @@
+//! Tests for OwnedErr, the cloneable and serializable form of TransErr.
+//!
+//! Tested:
+//!
+//! ✓ Clone + Send + Sync + UnwindSafe + Serialize + Error
+//! ✓ kind, message and position are kept
+//! ✓ the source chain is captured
+//! ✓ serialize to JSON
+//! ✓ send to another thread
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{io, panic::UnwindSafe, path::PathBuf, thread},
+};
+
+const INPUT: &str = "type, client, tx, amount
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 5.0
+";
+
+fn errors() -> Result<Vec<TransErr>, TransErr> {
+    let mut bank = Bank::new();
+    Ok(bank.process(CsvParse::new(INPUT.as_bytes())?).errors)
+}
+
+#[test]
+fn traits() {
+    fn assert_traits<
+        T: Clone + Send + Sync + UnwindSafe + serde::Serialize + std::error::Error + 'static,
+    >() {
+    }
+
+    assert_traits::<OwnedErr>();
+}
+
+#[test]
+fn from_trans_err() -> DynResult {
+    let errs = errors()?;
+    let owned = OwnedErr::from(&errs[0]);
+
+    assert_eq!(owned.kind, TransErrKind::InsufficientFunds);
+    assert_eq!(owned.message, errs[0].to_string().trim());
+    assert_eq!(owned.position.as_ref().map(|p| p.line), Some(3));
+    assert!(owned.sources.is_empty());
+
+    assert_eq!(owned.to_string(), format!("\n{}\n", owned.message));
+
+    Ok(())
+}
+
+#[test]
+fn sources() {
+    let err = TransErr::InputFile {
+        source: io::Error::new(io::ErrorKind::NotFound, "no such file"),
+        path: PathBuf::from("missing.csv"),
+    };
+
+    let owned = OwnedErr::from(err);
+
+    assert_eq!(owned.kind, TransErrKind::InputFile);
+    assert_eq!(owned.sources, vec!["no such file".to_string()]);
+    assert!(owned.message.contains("missing.csv"));
+}
+
+#[test]
+fn json() -> DynResult {
+    let errs = errors()?;
+    let json = serde_json::to_value(OwnedErr::from(&errs[0]))?;
+
+    assert_eq!(json["kind"], "InsufficientFunds");
+    assert_eq!(json["position"]["line"], 3);
+    assert_eq!(json["position"]["row"], "withdrawal, 1, 2, 5.0");
+    assert_eq!(json["sources"], serde_json::json!([]));
+    assert!(json["message"]
+        .as_str()
+        .unwrap()
+        .contains("insufficient funds"));
+
+    Ok(())
+}
+
+#[test]
+fn send() -> DynResult {
+    let owned: Vec<OwnedErr> = errors()?.iter().map(OwnedErr::from).collect();
+    let copy = owned.clone();
+
+    let kinds = thread::spawn(move || copy.iter().map(|e| e.kind).collect::<Vec<_>>())
+        .join()
+        .unwrap();
+
+    assert_eq!(kinds, vec![TransErrKind::InsufficientFunds]);
+    assert_eq!(owned.len(), 1);
+
+    Ok(())
+}
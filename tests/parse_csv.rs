@@ -14,10 +14,20 @@
 //!   ✓ invalid utf in header reports error
 //!   ✓ invalid utf in value causes just this transaction to be ignored
 //!   ✓ file with missing header reports error
-//!   - dispute, resolve, charge back with amount.
-//!   - deposit/withdraw without amount.
-//!   - non numeric values.
+//!   ✓ non numeric values.
+//!
+//! - Invalid transactions, each with their own DeserTransactKind:
+//!
+//!   ✓ negative amount
+//!   ✓ zero amount
+//!   ✓ unknown transaction type
+//!   ✓ deposit/withdraw without amount.
+//!   ✓ dispute, resolve, charge back with amount.
+//!   ✓ amount with more than four decimal places
+//!   ✓ amount that isn't a number
 //
+#![cfg(feature = "csv")]
+
 mod common;
 
 use {common::*, libtransact::*, pretty_assertions::assert_eq, std::path::Path};
@@ -27,7 +37,7 @@ fn file_input() -> DynResult {
     let parser = CsvParse::try_from(Path::new("tests/data/simple.csv"))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -51,7 +61,7 @@ fn empty_leading() -> DynResult {
     let parser = CsvParse::try_from(Path::new("tests/data/empty_leading.csv"))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -75,7 +85,7 @@ fn empty_trailing() -> DynResult {
     let parser = CsvParse::try_from(Path::new("tests/data/empty_trailing.csv"))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -99,7 +109,7 @@ fn empty_middle() -> DynResult {
     let parser = CsvParse::try_from(Path::new("tests/data/empty_middle.csv"))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 0, "{err:?}");
 
@@ -123,7 +133,7 @@ fn invalid_line() -> DynResult {
     let parser = CsvParse::try_from(Path::new("tests/data/invalid_line.csv"))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 2, "{err:?}");
     assert!(matches!(err[0], TransErr::DeserializeCsv { .. }));
@@ -154,7 +164,7 @@ fn invalid_utf8_in_header() {
 
     assert!(matches!(
         parser,
-        Err( TransErr::DeserializeHeader{ source } ) if matches!( source.kind(), &csv::ErrorKind::Utf8{..} )
+        Err( TransErr::DeserializeHeader{ source } ) if matches!( source.csv().map(csv::Error::kind), Some(csv::ErrorKind::Utf8{..}) )
     ));
 }
 
@@ -165,7 +175,7 @@ fn invalid_utf8_in_value() -> DynResult {
     let parser = CsvParse::try_from(Path::new("tests/data/invalid_utf8_in_value.csv"))?;
     let mut bank = Bank::new();
 
-    let err = bank.process(parser);
+    let err = bank.process(parser).errors;
 
     assert_eq!(err.len(), 1, "{err:?}");
 
@@ -183,3 +193,97 @@ fn invalid_utf8_in_value() -> DynResult {
 
     Ok(())
 }
+
+// Amounts are fixed point with four decimal places, more are rejected rather than rounded.
+//
+#[test]
+fn too_many_decimals() -> DynResult {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0001
+		   deposit,      1,  2,    1.00001
+
+	";
+
+    let mut bank = Bank::new();
+    let err = bank.process(CsvParse::try_from(input)?).errors;
+
+    assert_eq!(err.len(), 1, "{err:?}");
+    assert!(matches!(
+        err[0],
+        TransErr::DeserializeTransact {
+            kind: DeserTransactKind::TooManyDecimals,
+            ..
+        }
+    ));
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("1.0001"));
+
+    Ok(())
+}
+
+// Each invalid row is reported with the kind of problem.
+//
+#[test]
+fn deser_transact_kinds() -> DynResult {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    -1.0
+		   deposit,      1,  2,    0.0
+		withdrawal,      1,  3,    0
+		    refund,      1,  4,    1.0
+		   Deposit,      1,  5,    1.0
+		   deposit,      1,  6,
+		withdrawal,      1,  7,
+		   dispute,      1,  8,    1.0
+		   resolve,      1,  9,    1.0
+		chargeback,      1, 10,    1.0
+		   deposit,      1, 11,    1.00001
+		   deposit,      1, 12,    abc
+		   deposit,      1, 13,    1e3
+		   deposit,      1, 14,    99999999999999999999999999999999999999
+		   deposit,      1, 15,    1.5
+
+	";
+
+    let mut bank = Bank::new();
+    let err = bank.process(CsvParse::try_from(input)?).errors;
+
+    let kinds: Vec<_> = err
+        .iter()
+        .map(|e| match e {
+            TransErr::DeserializeTransact { kind, .. } => *kind,
+            e => panic!("unexpected error: {e}"),
+        })
+        .collect();
+
+    use DeserTransactKind::*;
+
+    assert_eq!(
+        kinds,
+        vec![
+            AmountNegative,   // tx 1
+            AmountZero,       // tx 2
+            AmountZero,       // tx 3
+            UnknownTransType, // tx 4
+            UnknownTransType, // tx 5
+            MissingAmount,    // tx 6
+            MissingAmount,    // tx 7
+            UnexpectedAmount, // tx 8
+            UnexpectedAmount, // tx 9
+            UnexpectedAmount, // tx 10
+            TooManyDecimals,  // tx 11
+            InvalidAmount,    // tx 12
+            InvalidAmount,    // tx 13
+            InvalidAmount,    // tx 14
+        ]
+    );
+
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("1.5"));
+
+    Ok(())
+}
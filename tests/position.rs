@@ -0,0 +1,162 @@
+//! Tests for the input position carried by transactions and errors.
+//!
+//! Tested:
+//!
+//! ✓ business rule errors point to the input line
+//! ✓ parse errors point to the input line
+//! ✓ the position is part of the error message, on its own line
+//! ✓ the row is kept as written, quotes included
+//! ✓ stored transactions don't keep the position
+//! ✓ positions of retained errors survive a snapshot
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+const INPUT: &str = "type, client, tx, amount
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 5.0
+deposit, 1, x, 1.0
+refund, 1, 4, 1.0
+deposit,1,5,\"1,5\"
+";
+
+fn process(bank: &mut Bank) -> Result<Vec<TransErr>, TransErr> {
+    Ok(bank.process(CsvParse::new(INPUT.as_bytes())?).errors)
+}
+
+#[test]
+fn bank_error() -> DynResult {
+    let mut bank = Bank::new();
+    let errs = process(&mut bank)?;
+
+    assert!(matches!(errs[0], TransErr::InsufficientFunds { .. }));
+    assert_eq!(
+        errs[0].position(),
+        Some(&Position {
+            line: 3,
+            byte: 44,
+            row: b"withdrawal, 1, 2, 5.0".to_vec(),
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parse_errors() -> DynResult {
+    let mut bank = Bank::new();
+    let errs = process(&mut bank)?;
+
+    assert_eq!(errs.len(), 4);
+
+    assert!(matches!(errs[1], TransErr::DeserializeCsv { .. }));
+    assert_eq!(errs[1].position().map(|p| p.line), Some(4));
+    assert_eq!(
+        errs[1].position().map(|p| p.row_text()),
+        Some("deposit, 1, x, 1.0".into())
+    );
+
+    assert!(matches!(errs[2], TransErr::DeserializeTransact { .. }));
+    assert_eq!(errs[2].position().map(|p| p.line), Some(5));
+    assert_eq!(
+        errs[2].position().map(|p| p.row_text()),
+        Some("refund, 1, 4, 1.0".into())
+    );
+
+    assert!(matches!(errs[3], TransErr::DeserializeTransact { .. }));
+    assert_eq!(
+        errs[3].position().map(|p| p.row_text()),
+        Some("deposit,1,5,\"1,5\"".into())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn display() -> DynResult {
+    let mut bank = Bank::new();
+    let errs = process(&mut bank)?;
+
+    let msg = errs[0].to_string();
+
+    assert!(
+        msg.contains("At line 3 (byte 44): \"withdrawal, 1, 2, 5.0\""),
+        "{msg}"
+    );
+
+    let msg = errs[1].to_string();
+
+    assert!(msg.contains("by it.\nAt line 4 (byte 66)"), "{msg}");
+
+    Ok(())
+}
+
+#[test]
+fn not_stored() -> DynResult {
+    let mut bank = Bank::new();
+    process(&mut bank)?;
+
+    let trans = bank.transactions().get(&1).unwrap();
+
+    assert_eq!(trans.pos, None);
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("1.0"));
+
+    Ok(())
+}
+
+#[test]
+fn snapshot() -> DynResult {
+    let mut bank = Bank::new();
+    bank.set_retain_errors(true);
+    process(&mut bank)?;
+
+    let mut data = Vec::new();
+    bank.snapshot(&mut data)?;
+
+    let restored = Bank::restore(data.as_slice())?;
+
+    let positions = |bank: &Bank| {
+        bank.errors()
+            .iter()
+            .map(|e| e.position().cloned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(positions(&restored), positions(&bank));
+    assert_eq!(restored.errors()[0].position().map(|p| p.line), Some(3));
+
+    Ok(())
+}
+
+#[test]
+fn raw_rows() -> DynResult {
+    let input = "\u{feff}type,client,tx,amount\r\n\r\n# comment\r\ndeposit, 1, 1, \"1.0\"\r\n\r\n\"with\r\ndrawal\",1,2,1.0\r\ndeposit,1\r\nrefund,1,3,1.0";
+
+    let rows = |parser: CsvParse<&[u8]>| {
+        parser
+            .map(|r| match r {
+                Ok(t) => t.pos.unwrap().row_text().into_owned(),
+                Err(e) => e.position().unwrap().row_text().into_owned(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let parser = CsvParseBuilder::new()
+        .comment(Some(b'#'))
+        .from_reader(input.as_bytes())?;
+
+    assert_eq!(
+        rows(parser),
+        [
+            "deposit, 1, 1, \"1.0\"",
+            "\"with\r\ndrawal\",1,2,1.0",
+            "deposit,1",
+            "refund,1,3,1.0"
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Per transaction outcomes through Bank::apply and Bank::process_iter.
+//!
+//! Tested:
+//!
+//! ✓ receipts carry the new state and the balances after each transaction
+//! ✓ errors are yielded in order and not accumulated in the bank
+//! ✓ apply a single transaction
+//! ✓ apply rejects administrative transactions
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+#[test]
+fn receipts() -> DynResult {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    2.0
+		withdrawal,      1,  2,    0.5
+		   dispute,      1,  1,
+		chargeback,      1,  1,
+
+	";
+
+    let parser = CsvParse::try_from(input)?;
+    let mut bank = Bank::new();
+    bank.set_dispute_policy(DisputePolicy::AllowNegative);
+
+    let receipts = bank.process_iter(parser).collect::<Result<Vec<_>, _>>()?;
+
+    let outcome: Vec<_> = receipts
+        .iter()
+        .map(|r| (r.trans.id, r.state, r.available, r.held, r.total, r.locked))
+        .collect();
+
+    assert_eq!(
+        outcome,
+        vec![
+            (1, TransState::Success, dec("2"), dec("0"), dec("2"), false),
+            (
+                2,
+                TransState::Success,
+                dec("1.5"),
+                dec("0"),
+                dec("1.5"),
+                false
+            ),
+            (
+                1,
+                TransState::Disputed,
+                dec("-0.5"),
+                dec("2"),
+                dec("1.5"),
+                false
+            ),
+            (
+                1,
+                TransState::ChargedBack,
+                dec("-0.5"),
+                dec("0"),
+                dec("-0.5"),
+                true
+            ),
+        ]
+    );
+
+    assert_eq!(receipts[2].trans.ttype, TransType::Dispute);
+
+    Ok(())
+}
+
+#[test]
+fn errors_yielded() -> DynResult {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    2.0
+		withdrawal,      1,  2,    5.0
+		   deposit,      1,  1,    1.0
+		   deposit,      1,  3,    1.0
+
+	";
+
+    let parser = CsvParse::try_from(input)?;
+    let mut bank = Bank::new();
+
+    let results: Vec<_> = bank.process_iter(parser).collect();
+
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok());
+    assert!(matches!(
+        results[1],
+        Err(TransErr::InsufficientFunds { .. })
+    ));
+    assert!(matches!(
+        results[2],
+        Err(TransErr::DuplicateTransact { .. })
+    ));
+    assert_eq!(results[3].as_ref().unwrap().total, dec("3"));
+
+    assert!(bank.errors().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn apply() -> DynResult {
+    let mut bank = Bank::new();
+
+    let receipt = bank.apply(Transact::new(TransType::Deposit(dec("1.5")), 7, 1))?;
+
+    assert_eq!(receipt.state, TransState::Success);
+    assert_eq!(receipt.available, dec("1.5"));
+    assert_eq!(receipt.total, dec("1.5"));
+
+    let err = bank.apply(Transact::new(TransType::WithDraw(dec("2")), 7, 2));
+
+    assert!(matches!(err, Err(TransErr::InsufficientFunds { .. })));
+    assert_eq!(bank.clients().get(&7).unwrap().available(), dec("1.5"));
+    assert!(bank.errors().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn apply_admin() -> DynResult {
+    let mut bank = Bank::new();
+
+    let err = bank.apply(Transact::new(TransType::Unlock("nope".into()), 1, 1));
+
+    assert!(matches!(err, Err(TransErr::Unauthorized { .. })));
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+//! Tests for writing rejected rows to a CSV file with RejectSink.
+//!
+//! Tested:
+//!
+//! ✓ parse-level and business-rule rejects, in the input schema with code, line and message
+//! ✓ fields are written as read, in the column order, delimiter and quoting of the input
+//! ✓ rows that can't be parsed are written as read
+//! ✓ errors are passed on to the next sink
+//! ✓ errors that aren't about a row are only passed on
+//! ✓ the rejects file can be parsed again after correction
+//! ✓ the cli --rejects flag, and failing to create the rejects file
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq, std::process::Command};
+
+const BATCH: &str = "
+	      type, client, tx, amount
+	   deposit,      1,  1,    1.0
+	   deposit,      1,  2,     -5
+	   payment,      1,  3,    1.0
+	   deposit,    one,  4,    1.0
+	withdrawal,      1,  5,    5.0
+	   dispute,      2,  1,
+";
+
+fn rejects(
+    input: impl Iterator<Item = Result<Transact, TransErr>>,
+    layout: &RejectLayout,
+) -> DynResult<(String, Vec<TransErr>)> {
+    let mut sink = RejectSink::with_layout(Vec::new(), layout, Vec::new());
+    Bank::new().process_into(input, &mut sink);
+
+    let (out, errors) = sink.finish()?;
+
+    Ok((String::from_utf8(out)?, errors))
+}
+
+// The columns before the message of each row.
+//
+fn columns(out: &str) -> Vec<String> {
+    let mut reader = csv::Reader::from_reader(out.as_bytes());
+
+    reader
+        .records()
+        .map(|r| r.unwrap().iter().take(6).collect::<Vec<_>>().join(","))
+        .collect()
+}
+
+// The rejects of BATCH, in the layout of the input.
+//
+fn batch() -> DynResult<(String, Vec<TransErr>)> {
+    let parser = CsvParse::try_from(BATCH)?;
+    let layout = parser.reject_layout().clone();
+
+    rejects(parser, &layout)
+}
+
+#[test]
+fn rejected_rows() -> DynResult {
+    let (out, errors) = batch()?;
+
+    assert!(out.starts_with("type,client,tx,amount,error_code,line,message\n"));
+
+    assert_eq!(
+        columns(&out),
+        [
+            "deposit,1,2,-5,E_INVALID_TX,3",
+            "payment,1,3,1.0,E_INVALID_TX,4",
+            "deposit,one,4,1.0,E_INVALID_ROW,5",
+            "withdrawal,1,5,5.0,E_INSUFFICIENT_FUNDS,6",
+            "dispute,2,1,,E_NO_CLIENT,7",
+        ]
+    );
+
+    assert_eq!(errors.len(), 5);
+
+    // The message is on one line, without the code and position that have their own columns.
+    //
+    let mut reader = csv::Reader::from_reader(out.as_bytes());
+
+    for record in reader.records() {
+        let message = record?[6].to_string();
+
+        assert!(!message.is_empty());
+        assert!(!message.contains('\n'));
+        assert!(!message.contains("E_"), "{message}");
+        assert!(!message.contains("At line"), "{message}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn unparsed_rows() -> DynResult {
+    let input = "{\"type\": \"deposit\", \"client\": 1, \"tx\": 1, \"amount\": \"1.0\"}\n\
+                 {\"type\": \"deposit\", \"client\": 1, tx: 2}\n";
+
+    let (out, _) = rejects(JsonLinesParse::new(input.as_bytes()), &RejectLayout::new())?;
+
+    let mut reader = csv::Reader::from_reader(out.as_bytes());
+    let records: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>()?;
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+        &records[0][0],
+        "{\"type\": \"deposit\", \"client\": 1, tx: 2}"
+    );
+    assert_eq!(
+        records[0].iter().skip(1).take(5).collect::<Vec<_>>(),
+        ["", "", "", "E_INVALID_ROW", "2"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn not_a_row() -> DynResult {
+    let mut sink = RejectSink::new(Vec::new(), Vec::new());
+
+    sink.report(TransErr::NoHeader);
+
+    assert_eq!(sink.count(), 0);
+
+    let (out, errors) = sink.finish()?;
+
+    assert_eq!(out, b"type,client,tx,amount,error_code,line,message\n");
+    assert!(matches!(errors[..], [TransErr::NoHeader]));
+
+    Ok(())
+}
+
+#[test]
+fn input_layout() -> DynResult {
+    let input = "
+		tx; type;       client; amount; note
+		 1; deposit;         1;     -5;\"a;b\"
+		 2; deposit;         1; 1.0000; x, y
+		 3; withdrawal;      2;    1.0;
+	";
+
+    let parser = CsvParseBuilder::new()
+        .delimiter(b';')
+        .from_reader(input.trim().as_bytes())?;
+
+    let layout = parser.reject_layout().clone();
+    let (out, _) = rejects(parser, &layout)?;
+
+    let rows: Vec<&str> = out.lines().map(|l| l.rsplit_once(';').unwrap().0).collect();
+
+    assert_eq!(
+        rows,
+        [
+            "tx;type;client;amount;note;error_code;line",
+            "1;deposit;1;-5;\"a;b\";E_INVALID_TX;2",
+            "3;withdrawal;2;1.0;;E_NO_CLIENT;4",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn resubmit() -> DynResult {
+    let (out, _) = batch()?;
+
+    // The extra columns are ignored.
+    //
+    let corrected = out.replace("-5", "5").replace("payment", "deposit");
+    let parsed: Vec<_> = CsvParse::new(corrected.as_bytes())?.collect();
+
+    assert_eq!(parsed.len(), 5);
+    assert_eq!(
+        parsed[0].as_ref().unwrap().ttype,
+        TransType::Deposit(dec("5"))
+    );
+    assert_eq!(
+        parsed[1].as_ref().unwrap().ttype,
+        TransType::Deposit(dec("1"))
+    );
+    assert!(parsed[2].is_err());
+
+    Ok(())
+}
+
+#[test]
+fn cli() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let input = dir.path().join("batch.csv");
+    let rejects = dir.path().join("rejects.csv");
+
+    std::fs::write(&input, BATCH.trim())?;
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--rejects"])
+        .arg(&rejects)
+        .arg(&input)
+        .output()?;
+
+    // Errors still go to stderr as well.
+    //
+    assert_eq!(output.status.code(), Some(5));
+    assert!(std::str::from_utf8(&output.stderr)?.contains("E_NO_CLIENT"));
+
+    let out = std::fs::read_to_string(&rejects)?;
+
+    assert_eq!(columns(&out).len(), 5);
+    assert_eq!(columns(&out)[4], "dispute,2,1,,E_NO_CLIENT,7");
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--rejects"])
+        .arg(dir.path().join("missing/rejects.csv"))
+        .arg(&input)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(std::str::from_utf8(&output.stderr)?.contains("E_REJECTS"));
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Tests for the ProcessReport returned by Bank::process.
+//!
+//! Tested:
+//!
+//! ✓ counts of applied and rejected transactions and clients touched
+//! ✓ counts of rejected transactions by kind of error, also when errors go to a sink
+//! ✓ each report only contains the errors of its own batch
+//! ✓ errors are moved into the bank when retaining errors
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+const BATCH: &str = "
+
+	      type, client, tx, amount
+	   deposit,      1,  1,    1.0
+	   deposit,      2,  2,    2.0
+	withdrawal,      1,  3,    5.0
+	   deposit,      1,  4,    1.0
+	   dispute,      3,  1,
+
+";
+
+#[test]
+fn counts() -> DynResult {
+    let mut bank = Bank::new();
+
+    let report = bank.process(CsvParse::try_from(BATCH)?);
+
+    assert_eq!(report.succeeded, 3);
+    assert_eq!(report.failed, 2);
+    assert_eq!(report.processed(), 5);
+    assert_eq!(report.clients_touched, 2);
+    assert_eq!(report.errors.len(), 2);
+    assert_eq!(report.failed_by_kind.len(), 2);
+    assert_eq!(report.failed_with(TransErrKind::InsufficientFunds), 1);
+    assert_eq!(report.failed_with(TransErrKind::NoClient), 1);
+    assert_eq!(report.failed_with(TransErrKind::Overflow), 0);
+    assert!(matches!(
+        report.errors[0],
+        TransErr::InsufficientFunds { .. }
+    ));
+    assert!(matches!(report.errors[1], TransErr::NoClient { .. }));
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("2.0"));
+
+    Ok(())
+}
+
+#[test]
+fn per_batch() -> DynResult {
+    let mut bank = Bank::new();
+
+    bank.process(CsvParse::try_from(BATCH)?);
+
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		withdrawal,      2,  5,    1.0
+
+	";
+
+    let report = bank.process(CsvParse::try_from(input)?);
+
+    assert_eq!(report.succeeded, 1);
+    assert_eq!(report.clients_touched, 1);
+    assert_eq!(report.errors.len(), 1);
+    assert!(matches!(
+        report.errors[0],
+        TransErr::DuplicateTransact { .. }
+    ));
+    assert!(bank.errors().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn retain_errors() -> DynResult {
+    let mut bank = Bank::new();
+    bank.set_retain_errors(true);
+
+    let report = bank.process(CsvParse::try_from(BATCH)?);
+
+    assert_eq!(report.failed, 2);
+    assert!(report.errors.is_empty());
+    assert_eq!(bank.errors().len(), 2);
+
+    bank.process(CsvParse::try_from(BATCH)?);
+
+    assert_eq!(bank.errors().len(), 7);
+
+    Ok(())
+}
+
+#[test]
+fn failed_by_kind_sink() -> DynResult {
+    let mut bank = Bank::new();
+
+    bank.process(CsvParse::try_from(BATCH)?);
+
+    let report = bank.process_into(CsvParse::try_from(BATCH)?, CountSink::new());
+
+    assert_eq!(report.failed, 5);
+    assert!(report.errors.is_empty());
+    assert_eq!(report.failed_with(TransErrKind::DuplicateTransact), 3);
+    assert_eq!(report.failed_with(TransErrKind::InsufficientFunds), 1);
+    assert_eq!(report.failed_with(TransErrKind::NoClient), 1);
+    assert_eq!(report.failed_by_kind.values().sum::<usize>(), report.failed);
+
+    Ok(())
+}
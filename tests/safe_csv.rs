@@ -0,0 +1,223 @@
+//! Differential tests of SafeCsvParse against CsvParse. Both parsers must produce the same
+//! transactions, and the same kind of errors at the same positions.
+//!
+//! Tested:
+//!
+//! ✓ all files in tests/data
+//! ✓ quoting edge cases
+//! ✓ line endings and blank lines
+//! ✓ byte order mark
+//! ✓ invalid utf8 in a row, reported without an error of the csv crate
+//! ✓ wrong number of fields and invalid numbers
+//! ✓ invalid header
+//! ✓ layouts of columns, with and without header
+//
+#![cfg(all(feature = "safe_csv", feature = "csv"))]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{fs, path::Path},
+};
+
+// What we compare: the transaction, or the kind and position of the error.
+//
+#[derive(Debug, PartialEq)]
+//
+enum Outcome {
+    Trans(Transact),
+    Err(TransErrKind, Option<DeserTransactKind>, Option<Position>),
+}
+
+fn outcome(item: Result<Transact, TransErr>) -> Outcome {
+    match item {
+        Ok(t) => Outcome::Trans(t),
+
+        Err(e) => {
+            let kind = match &e {
+                TransErr::DeserializeTransact { kind, .. } => Some(*kind),
+                _ => None,
+            };
+
+            Outcome::Err(e.kind(), kind, e.position().cloned())
+        }
+    }
+}
+
+fn outcomes(
+    parser: Result<impl Iterator<Item = Result<Transact, TransErr>>, TransErr>,
+) -> Result<Vec<Outcome>, TransErrKind> {
+    parser
+        .map(|p| p.map(outcome).collect())
+        .map_err(|e| e.kind())
+}
+
+fn compare(input: &[u8]) {
+    compare_with(input, &CsvLayout::default());
+}
+
+fn compare_with(input: &[u8], layout: &CsvLayout) {
+    let expect = outcomes(CsvParse::with_layout(input, layout));
+    let actual = outcomes(SafeCsvParse::with_layout(input, layout));
+
+    assert_eq!(
+        actual,
+        expect,
+        "input: {:?}",
+        String::from_utf8_lossy(input)
+    );
+}
+
+#[test]
+fn data_files() -> DynResult {
+    let mut count = 0;
+
+    for entry in fs::read_dir("tests/data")? {
+        let path = entry?.path();
+
+        compare(&fs::read(&path)?);
+
+        let expect = outcomes(CsvParse::try_from(path.as_path()));
+        let actual = outcomes(SafeCsvParse::try_from(path.as_path()));
+
+        assert_eq!(actual, expect, "file: {path:?}");
+
+        count += 1;
+    }
+
+    assert!(count >= 9);
+
+    Ok(())
+}
+
+#[test]
+fn quoting() {
+    compare(b"type,client,tx,amount\n\"deposit\",1,1,\"1.5\"\n");
+    compare(b"type,client,tx,amount\n\"dep\"\"osit\",1,1,1.5\n");
+    compare(b"type,client,tx,amount\n\"dep\"osit,1,1,1.5\n");
+    compare(b"type,client,tx,amount\n\"with,comma\",1,1,1.5\n");
+    compare(b"type,client,tx,amount\n\"multi\nline\",1,1,1.5\ndeposit,1,2,1\n");
+    compare(b"type,client,tx,amount\n  \"deposit\"  ,1,1,1.5\n");
+    compare(b"type,client,tx,amount\n\" deposit \",1,1,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,1,1,\"\"\n");
+    compare(b"type,client,tx,amount\ndep\"osit,1,1,1.5\n");
+    compare(b"\"type\",\"client\",\"tx\",\"amount\"\ndeposit,1,1,1.5\n");
+    compare(b"type,client,tx,amount\n\"unterminated,1,1,1.5\n");
+}
+
+#[test]
+fn line_endings() {
+    compare(b"type,client,tx,amount\r\ndeposit,1,1,1.5\r\ndeposit,1,2,2\r\n");
+    compare(b"type,client,tx,amount\rdeposit,1,1,1.5\rdeposit,1,2,2");
+    compare(b"type,client,tx,amount\ndeposit,1,1,1.5");
+    compare(b"\n\n  type, client, tx, amount\n\n\ndeposit, 1, 1, 1.5\n\n\n");
+    compare(b"type,client,tx,amount\r\n\r\ndeposit,1,1,1.5\r\n\r\n");
+    compare(b"type,client,tx,amount\n   \ndeposit,1,1,1.5\n");
+    compare(b"type,client,tx,amount\n\t\ndeposit,1,1,1.5\n");
+}
+
+#[test]
+fn bom() {
+    compare(b"\xEF\xBB\xBFtype,client,tx,amount\ndeposit,1,1,1.5\n");
+    compare(b"\xEF\xBB\xBF\xEF\xBB\xBFtype,client,tx,amount\ndeposit,1,1,1.5\n");
+}
+
+#[test]
+fn invalid_utf8() -> DynResult {
+    compare(b"type,client,tx,amount\ndeposit,1,1,1.5\ndep\xFFosit,1,2,1.5\ndeposit,1,3,1\n");
+    compare(b"typ\xFFe,client,tx,amount\ndeposit,1,1,1.5\n");
+
+    let input: &[u8] = b"type,client,tx,amount\ndep\xFFosit,1,2,1.5\n";
+
+    match SafeCsvParse::new(input)?.next() {
+        Some(Err(TransErr::DeserializeCsv { source, .. })) => assert!(source.csv().is_none()),
+        other => panic!("unexpected: {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_rows() {
+    compare(b"type,client,tx,amount\ndeposit,1,1\ndeposit,1,2,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,1,1,1.5,extra\n");
+    compare(b"type,client,tx,amount\ndeposit\n");
+    compare(b"type,client,tx,amount\ndeposit,x,1,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,1,-1,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,70000,1,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,,1,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,+1,007,1.5\n");
+    compare(b"type,client,tx,amount\ndeposit,1,1,-1.5\nrefund,1,2,1\ndispute,1,1,1\n");
+    compare(b"type,client,tx,amount\ndeposit,1,1,1.00001\nwithdrawal,1,2,\n");
+}
+
+#[test]
+fn header() {
+    compare(b"");
+    compare(b"\n\n");
+    compare(b"deposit,1,1,1.5\n");
+    compare(b"type,client,tx\ndeposit,1,1\n");
+    compare(b"Type,Client,Tx,Amount\n");
+    compare(b"type,client,tx,amount");
+    compare(b"type,client,tx,amount\n");
+}
+
+#[test]
+fn layouts() {
+    compare(b"TX,amount,Client,type\n1,1.5,1,deposit\n");
+    compare(b"type,client,tx,amount,type\ndeposit,1,1,1.5,deposit\n");
+    compare(b"note,type,client,tx,amount\nx,deposit,1,1,1.5\ny,deposit,1,2\n");
+
+    let mut layout = CsvLayout::new();
+    layout.set_extra_columns(ExtraColumns::PassThrough);
+
+    compare_with(
+        b"note,type,client,tx,amount\nx,deposit,1,1,1.5\n,deposit,1,2,1\n",
+        &layout,
+    );
+
+    let mut layout = CsvLayout::headerless([
+        Column::Amount,
+        Column::Tx,
+        Column::Extra("note".to_string()),
+        Column::Client,
+        Column::Type,
+    ]);
+
+    compare_with(b"1.5,1,x,1,deposit\n2,2,y,1,deposit\n", &layout);
+    compare_with(b"1.5,1,1,deposit\n2,2,y,1,deposit\n", &layout);
+    compare_with(b"1.5,1,x,1,deposit\n2,2,1,deposit\n", &layout);
+    compare_with(b"\n\n1.5,1,x,1,dep\xFFosit\n", &layout);
+    compare_with(b"", &layout);
+
+    layout.set_extra_columns(ExtraColumns::PassThrough);
+
+    compare_with(b"1.5,1,x,1,deposit\r\n2,2,y,1,deposit\r\n", &layout);
+}
+
+#[test]
+fn try_from_str() -> DynResult {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		withdrawal,      1,  2,    0.5
+
+	";
+
+    let mut bank = Bank::new();
+    let errs = bank.process(SafeCsvParse::try_from(input)?).errors;
+
+    assert!(errs.is_empty());
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("0.5"));
+
+    assert!(matches!(
+        SafeCsvParse::try_from(Path::new("tests/data/does_not_exist.csv")),
+        Err(TransErr::InputFile { .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Tests for SafeCsvParse that don't need the csv crate, so they also run with
+//! `--no-default-features --features safe_csv`.
+//!
+//! Tested:
+//!
+//! ✓ a file is processed into the right balances
+//! ✓ invalid rows are reported without an error of the csv crate
+//! ✓ a row longer than max_row_len ends the input, also in an unterminated quote
+//
+#![cfg(feature = "safe_csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{fmt::Write as _, path::Path},
+};
+
+#[test]
+fn process_file() -> DynResult {
+    let mut bank = Bank::new();
+    let errs = bank
+        .process(SafeCsvParse::try_from(Path::new("tests/data/simple.csv"))?)
+        .errors;
+
+    assert!(errs.is_empty(), "{errs:?}");
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("1.5"));
+    assert_eq!(bank.clients().get(&2).unwrap().total(), dec("1.9"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_row() -> DynResult {
+    let input = "type,client,tx,amount\ndeposit,1\ndeposit,1,2,1.5\n";
+    let results: Vec<_> = SafeCsvParse::try_from(input)?.collect();
+
+    assert_eq!(results.len(), 2);
+
+    match &results[0] {
+        Err(e @ TransErr::DeserializeCsv { source, .. }) => {
+            assert_eq!(e.position().map(|p| p.line), Some(2));
+            assert!(!source.to_string().is_empty());
+        }
+
+        other => panic!("unexpected: {other:?}"),
+    }
+
+    assert_eq!(results[1].as_ref().unwrap().id, 2);
+
+    Ok(())
+}
+
+#[test]
+fn max_row_len() -> DynResult {
+    let mut input = String::from("type,client,tx,amount\ndeposit,1,1,1.5\n");
+
+    writeln!(input, "deposit,1,2,\"1.{}", "0\n".repeat(1000))?;
+
+    let parser = SafeCsvParse::with_max_row_len(input.as_bytes(), &CsvLayout::default(), Some(40))?;
+    let results: Vec<_> = parser.collect();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().id, 1);
+
+    match &results[1] {
+        Err(e @ TransErr::DeserializeCsv { source, .. }) => {
+            assert_eq!(e.position().map(|p| p.line), Some(3));
+            assert!(source.to_string().contains("maximum of 40 bytes"));
+        }
+
+        other => panic!("unexpected: {other:?}"),
+    }
+
+    // The header counts as well.
+    //
+    let parser = SafeCsvParse::with_max_row_len(input.as_bytes(), &CsvLayout::default(), Some(10));
+
+    assert!(matches!(parser, Err(TransErr::DeserializeHeader { .. })));
+
+    Ok(())
+}
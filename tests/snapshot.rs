@@ -0,0 +1,225 @@
+//! Tests for persisting a bank between runs with snapshot and restore.
+//!
+//! Tested:
+//!
+//! ✓ clients, transactions with their state and errors survive a round trip
+//! ✓ dispute a deposit from before the snapshot
+//! ✓ locked accounts stay locked
+//! ✓ reject data that isn't a snapshot
+//! ✓ reject a truncated snapshot
+//! ✓ run binary twice with a state file
+//! ✓ leave out the transactions of a durable store
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {
+    common::*,
+    libtransact::*,
+    pretty_assertions::assert_eq,
+    std::{collections::HashMap, process::Command},
+};
+
+fn day1() -> Bank {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		   deposit,      1,  2,    2.0
+		   deposit,      2,  3,    5.0
+		   dispute,      2,  3,
+		chargeback,      2,  3,
+		withdrawal,      1,  4,    9.0
+
+	";
+
+    let mut bank = Bank::new();
+    bank.set_retain_errors(true);
+
+    let report = bank.process(CsvParse::try_from(input).unwrap());
+
+    assert_eq!(report.failed, 1);
+    assert_eq!(bank.errors().len(), 1);
+    bank
+}
+
+#[test]
+fn round_trip() -> DynResult {
+    let bank = day1();
+    let mut data = Vec::new();
+
+    bank.snapshot(&mut data)?;
+
+    let restored = Bank::restore(data.as_slice())?;
+
+    assert_eq!(restored.clients(), bank.clients());
+    assert_eq!(restored.transactions(), bank.transactions());
+
+    assert_eq!(restored.errors().len(), 1);
+    assert!(matches!(
+        restored.errors()[0],
+        TransErr::InsufficientFunds {
+            trans: Transact {
+                ttype: TransType::WithDraw(_),
+                client: 1,
+                id: 4,
+                ..
+            }
+        }
+    ));
+
+    assert_eq!(
+        restored.transactions().get(&3).unwrap().state,
+        TransState::ChargedBack
+    );
+
+    Ok(())
+}
+
+#[test]
+fn dispute_after_restore() -> DynResult {
+    let mut data = Vec::new();
+    day1().snapshot(&mut data)?;
+
+    let mut bank = Bank::restore(data.as_slice())?;
+
+    let input = "
+
+		      type, client, tx, amount
+		   dispute,      1,  1,
+		   deposit,      2,  5,    1.0
+
+	";
+
+    let errs = bank.process(CsvParse::try_from(input)?).errors;
+
+    // Only the locked account of client 2, not the error retained from day 1.
+    //
+    assert_eq!(errs.len(), 1);
+    assert!(matches!(errs[0], TransErr::AccountLocked { .. }));
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert_eq!(client.available(), dec("2.0"));
+    assert_eq!(client.held(), dec("1.0"));
+    assert_eq!(client.total(), dec("3.0"));
+
+    Ok(())
+}
+
+// The log already has the transactions, so neither snapshot nor restore copy them.
+//
+#[test]
+fn durable_store() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transactions.log");
+
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		   deposit,      1,  2,    2.0
+		   dispute,      1,  2,
+
+	";
+
+    let mut bank = Bank::with_stores(FileStore::open(&path)?, HashMap::new());
+    assert!(bank.process(CsvParse::try_from(input)?).errors.is_empty());
+
+    let mut data = Vec::new();
+    bank.snapshot(&mut data)?;
+
+    let clients = bank.clients().clone();
+    drop(bank);
+
+    let len = std::fs::metadata(&path)?.len();
+
+    let mut bank =
+        Bank::restore_with_stores(data.as_slice(), FileStore::open(&path)?, HashMap::new())?;
+
+    assert_eq!(std::fs::metadata(&path)?.len(), len);
+    assert_eq!(bank.clients(), &clients);
+    assert_eq!(bank.transactions().len(), 2);
+
+    let input = "
+
+		      type, client, tx, amount
+		   resolve,      1,  2,
+
+	";
+
+    assert!(bank.process(CsvParse::try_from(input)?).errors.is_empty());
+    assert_eq!(bank.clients().get(&1).unwrap().available(), dec("3.0"));
+
+    // Without the log, the transactions would be missing.
+    //
+    assert!(matches!(
+        Bank::restore(data.as_slice()),
+        Err(TransErr::Snapshot { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn not_a_snapshot() {
+    let data = b"type, client, tx, amount\n";
+
+    assert!(matches!(
+        Bank::restore(data.as_slice()),
+        Err(TransErr::Snapshot { .. })
+    ));
+}
+
+#[test]
+fn truncated() -> DynResult {
+    let mut data = Vec::new();
+    day1().snapshot(&mut data)?;
+
+    data.pop();
+
+    assert!(matches!(
+        Bank::restore(data.as_slice()),
+        Err(TransErr::Snapshot { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn cli_state() -> DynResult {
+    let dir = tempfile::tempdir()?;
+    let state = dir.path().join("bank.state");
+    let day2 = dir.path().join("day2.csv");
+
+    std::fs::write(&day2, "type, client, tx, amount\ndispute, 1, 1,\n")?;
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("--state")
+        .arg(&state)
+        .arg("tests/data/simple.csv")
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(state.exists());
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("--state")
+        .arg(&state)
+        .arg(&day2)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(0));
+
+    let out = std::str::from_utf8(&output.stdout)?;
+
+    assert!(out.contains("1,        0.5,          1,        1.5,      false"));
+    assert!(out.contains("2,        1.9,          0,        1.9,      false"));
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+//! Tests for reinstating locked accounts.
+//!
+//! Tested:
+//!
+//! ✓ unlock through the API after a chargeback
+//! ✓ unlock through a privileged source
+//! ✓ unlock from a partner source is unauthorized
+//! ✓ unlock an account that isn't locked
+//! ✓ unlock with an existing transaction id
+//! ✓ the unlock reason survives a snapshot
+//! ✓ parse unlocks from admin csv and JSON Lines input
+//! ✓ unlocks from partner input are an unknown type
+//! ✓ admin input without a reason
+//
+#![cfg(feature = "csv")]
+
+mod common;
+
+use {common::*, libtransact::*, pretty_assertions::assert_eq};
+
+fn charged_back() -> Bank {
+    let input = "
+
+		      type, client, tx, amount
+		   deposit,      1,  1,    1.0
+		   deposit,      1,  2,    2.0
+		   dispute,      1,  2,
+		chargeback,      1,  2,
+
+	";
+
+    let mut bank = Bank::new();
+    let err = bank.process(CsvParse::try_from(input).unwrap()).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+    assert!(bank.clients().get(&1).unwrap().is_locked());
+
+    bank
+}
+
+#[test]
+fn unlock_api() -> DynResult {
+    let mut bank = charged_back();
+
+    bank.unlock(1, 3, "Chargeback was fraudulent")?;
+
+    let client = bank.clients().get(&1).unwrap();
+
+    assert!(!client.is_locked());
+    assert_eq!(client.unlock_reason(), Some("Chargeback was fraudulent"));
+
+    assert_eq!(
+        bank.transactions().get(&3),
+        Some(&Transact {
+            ttype: TransType::Unlock("Chargeback was fraudulent".to_string()),
+            state: TransState::Success,
+            client: 1,
+            id: 3,
+            pos: None,
+            extra: Vec::new(),
+            held: Amount::ZERO,
+        })
+    );
+
+    // Transactions are accepted again.
+    //
+    let input = "type, client, tx, amount\ndeposit, 1, 4, 1.5\n";
+    let err = bank.process(CsvParse::new(input.as_bytes())?).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+    assert_eq!(bank.clients().get(&1).unwrap().total(), dec("2.5"));
+
+    Ok(())
+}
+
+#[test]
+fn unlock_admin_source() {
+    let mut bank = charged_back();
+
+    let trs: Vec<Result<_, TransErr>> = vec![Ok(Transact::new(
+        TransType::Unlock("Reviewed".to_string()),
+        1,
+        3,
+    ))];
+
+    let err = bank.process_admin(trs.into_iter()).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+    assert!(!bank.clients().get(&1).unwrap().is_locked());
+}
+
+#[test]
+fn unlock_unauthorized() {
+    let mut bank = charged_back();
+
+    let trs: Vec<Result<_, TransErr>> = vec![Ok(Transact::new(
+        TransType::Unlock("Let me in".to_string()),
+        1,
+        3,
+    ))];
+
+    let err = bank.process(trs.into_iter()).errors;
+
+    assert_eq!(err.len(), 1);
+    assert!(matches!(err[0], TransErr::Unauthorized { .. }));
+
+    assert!(bank.clients().get(&1).unwrap().is_locked());
+    assert!(bank.transactions().get(&3).is_none());
+}
+
+#[test]
+fn unlock_not_locked() {
+    let mut bank = charged_back();
+
+    bank.clients_mut().get_mut(&1).unwrap().unlock("test");
+
+    assert!(matches!(
+        bank.unlock(1, 3, "again"),
+        Err(TransErr::AccountNotLocked { .. })
+    ));
+
+    assert!(bank.transactions().get(&3).is_none());
+}
+
+#[test]
+fn unlock_duplicate() {
+    let mut bank = charged_back();
+
+    assert!(matches!(
+        bank.unlock(1, 2, "Reviewed"),
+        Err(TransErr::DuplicateTransact { .. })
+    ));
+
+    assert!(bank.clients().get(&1).unwrap().is_locked());
+}
+
+#[test]
+fn unlock_snapshot() -> DynResult {
+    let mut bank = charged_back();
+    bank.unlock(1, 3, "Reviewed")?;
+
+    let mut data = Vec::new();
+    bank.snapshot(&mut data)?;
+
+    let restored = Bank::restore(data.as_slice())?;
+
+    assert_eq!(restored.clients(), bank.clients());
+    assert_eq!(restored.transactions(), bank.transactions());
+
+    Ok(())
+}
+
+#[test]
+fn unlock_parse_admin() -> DynResult {
+    let mut bank = charged_back();
+
+    let input = "
+
+		  type, client, tx, amount,                    reason
+		unlock,      1,  3,       , Chargeback was fraudulent
+
+	";
+
+    let parser = CsvParseBuilder::new()
+        .admin(true)
+        .from_reader(input.trim().as_bytes())?;
+
+    let err = bank.process_admin(parser).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+    assert_eq!(
+        bank.clients().get(&1).unwrap().unlock_reason(),
+        Some("Chargeback was fraudulent")
+    );
+
+    let mut bank = charged_back();
+
+    let input = r#"{"type": "unlock", "client": 1, "tx": 3, "reason": "Reviewed"}"#;
+    let mut parser = JsonLinesParse::new(input.as_bytes());
+    parser.set_admin(true);
+
+    let err = bank.process_admin(parser).errors;
+
+    assert!(err.is_empty(), "{err:?}");
+    assert_eq!(
+        bank.clients().get(&1).unwrap().unlock_reason(),
+        Some("Reviewed")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unlock_parse_partner() -> DynResult {
+    let input = "
+
+		  type, client, tx, amount,   reason
+		unlock,      1,  3,       , Reviewed
+
+	";
+
+    let trans: Vec<_> = CsvParse::try_from(input)?.collect();
+
+    assert_eq!(trans.len(), 1);
+    assert!(matches!(
+        trans[0],
+        Err(TransErr::DeserializeTransact {
+            kind: DeserTransactKind::UnknownTransType,
+            ..
+        })
+    ));
+
+    let input = r#"{"type": "unlock", "client": 1, "tx": 3, "reason": "Reviewed"}"#;
+    let trans: Vec<_> = JsonLinesParse::new(input.as_bytes()).collect();
+
+    assert!(matches!(
+        trans[0],
+        Err(TransErr::DeserializeTransact {
+            kind: DeserTransactKind::UnknownTransType,
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn unlock_parse_no_reason() -> DynResult {
+    let input = "
+
+		  type, client, tx, amount, reason
+		unlock,      1,  3,       ,
+		unlock,      1,  4,    1.0, Reviewed
+
+	";
+
+    let kinds: Vec<_> = CsvParseBuilder::new()
+        .admin(true)
+        .from_reader(input.trim().as_bytes())?
+        .map(|r| match r {
+            Err(TransErr::DeserializeTransact { kind, .. }) => Some(kind),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        kinds,
+        [
+            Some(DeserTransactKind::MissingReason),
+            Some(DeserTransactKind::UnexpectedAmount)
+        ]
+    );
+
+    // Without a reason column.
+    //
+    let input = "type, client, tx, amount\nunlock, 1, 3,";
+
+    let trans: Vec<_> = CsvParseBuilder::new()
+        .admin(true)
+        .from_reader(input.as_bytes())?
+        .collect();
+
+    assert!(matches!(
+        trans[0],
+        Err(TransErr::DeserializeTransact {
+            kind: DeserTransactKind::MissingReason,
+            ..
+        })
+    ));
+
+    Ok(())
+}